@@ -1,3 +1,7 @@
+mod vector;
+
+pub use vector::*;
+
 pub trait Solution: Default {
     fn part1(&self) -> String;
     fn part2(&self) -> String;
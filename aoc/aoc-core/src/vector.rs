@@ -0,0 +1,262 @@
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use num_traits::{Signed, Zero};
+
+/// A vector with `N` components, for positions and velocities on grids and in space.
+///
+/// Components are read with [`Index`] or, for 2 and 3 dimensions, with
+/// the named accessors `x()`, `y()` and `z()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vector<T, const N: usize>([T; N]);
+
+/// A vector in the plane, built with [`Vector2::new`].
+pub type Vector2<T> = Vector<T, 2>;
+
+/// A vector in space, built with [`Vector3::new`].
+pub type Vector3<T> = Vector<T, 3>;
+
+impl<T, const N: usize> Vector<T, N> {
+    pub const fn from_array(components: [T; N]) -> Self {
+        Self(components)
+    }
+
+    pub fn components(&self) -> &[T; N] {
+        &self.0
+    }
+
+    pub fn into_array(self) -> [T; N] {
+        self.0
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    /// Apply `f` to every component.
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> Vector<U, N> {
+        Vector(self.0.map(f))
+    }
+}
+
+impl<T: Zero + Copy, const N: usize> Vector<T, N> {
+    pub fn zero() -> Self {
+        Self([T::zero(); N])
+    }
+
+    /// The sum of the products of the components.
+    pub fn dot(&self, other: &Self) -> T
+    where
+        T: Mul<Output = T>,
+    {
+        self.iter()
+            .zip(other.iter())
+            .fold(T::zero(), |sum, (&a, &b)| sum + a * b)
+    }
+}
+
+impl<T: Signed + Copy + PartialOrd, const N: usize> Vector<T, N> {
+    /// The sum of the absolute values of the components, i.e. the taxicab distance from the origin.
+    pub fn manhattan(&self) -> T {
+        self.iter().fold(T::zero(), |sum, c| sum + c.abs())
+    }
+
+    /// The largest absolute value of the components, i.e. the number of king moves from the origin.
+    pub fn chebyshev(&self) -> T {
+        self.iter().fold(T::zero(), |max, c| {
+            let c = c.abs();
+            if c > max {
+                c
+            } else {
+                max
+            }
+        })
+    }
+}
+
+impl<T: Copy> Vector2<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self([x, y])
+    }
+
+    pub fn x(&self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> T {
+        self.0[1]
+    }
+}
+
+impl<T: Copy> Vector3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Self([x, y, z])
+    }
+
+    pub fn x(&self) -> T {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> T {
+        self.0[1]
+    }
+
+    pub fn z(&self) -> T {
+        self.0[2]
+    }
+}
+
+impl<T: Default, const N: usize> Default for Vector<T, N> {
+    fn default() -> Self {
+        Self(std::array::from_fn(|_| T::default()))
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T, N> {
+    fn from(components: [T; N]) -> Self {
+        Self(components)
+    }
+}
+
+impl<T, const N: usize> From<Vector<T, N>> for [T; N] {
+    fn from(vector: Vector<T, N>) -> Self {
+        vector.0
+    }
+}
+
+impl<T, const N: usize> Index<usize> for Vector<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.0[index]
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for Vector<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.0[index]
+    }
+}
+
+impl<T: Display, const N: usize> Display for Vector<T, N> {
+    /// Format the components like a tuple, e.g. `(1, -2, 3)`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (i, c) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl<T: AddAssign, const N: usize> AddAssign for Vector<T, N> {
+    fn add_assign(&mut self, other: Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a += b;
+        }
+    }
+}
+
+impl<T: AddAssign, const N: usize> Add for Vector<T, N> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<T: SubAssign, const N: usize> SubAssign for Vector<T, N> {
+    fn sub_assign(&mut self, other: Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a -= b;
+        }
+    }
+}
+
+impl<T: SubAssign, const N: usize> Sub for Vector<T, N> {
+    type Output = Self;
+
+    fn sub(mut self, other: Self) -> Self {
+        self -= other;
+        self
+    }
+}
+
+impl<T: Neg<Output = T>, const N: usize> Neg for Vector<T, N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.map(|c| -c)
+    }
+}
+
+impl<T: MulAssign + Copy, const N: usize> MulAssign<T> for Vector<T, N> {
+    fn mul_assign(&mut self, scalar: T) {
+        for c in self.0.iter_mut() {
+            *c *= scalar;
+        }
+    }
+}
+
+impl<T: MulAssign + Copy, const N: usize> Mul<T> for Vector<T, N> {
+    type Output = Self;
+
+    fn mul(mut self, scalar: T) -> Self {
+        self *= scalar;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Vector3::<i32>::new(1, -2, 3);
+        let b = Vector::from([4, 5, -6]);
+        assert_eq!(a + b, Vector3::new(5, 3, -3));
+        assert_eq!(a - b, Vector3::new(-3, -7, 9));
+        assert_eq!(-a, Vector3::new(-1, 2, -3));
+        assert_eq!(a * 2, Vector3::new(2, -4, 6));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        c *= -1;
+        assert_eq!(c, -b);
+    }
+
+    #[test]
+    fn products_and_norms() {
+        let a = Vector3::<i64>::new(1, -2, 3);
+        let b = Vector3::new(4, 5, -6);
+        assert_eq!(a.dot(&b), 4 - 10 - 18);
+        assert_eq!(a.manhattan(), 6);
+        assert_eq!(a.chebyshev(), 3);
+        assert_eq!((a - b).manhattan(), 19);
+        assert_eq!((a - b).chebyshev(), 9);
+        assert_eq!(Vector::<i32, 4>::zero().manhattan(), 0);
+        assert_eq!(Vector2::<f64>::new(-1.5, 0.5).chebyshev(), 1.5);
+    }
+
+    #[test]
+    fn components() {
+        let mut v = Vector2::new(7, 8);
+        assert_eq!((v.x(), v.y()), (7, 8));
+        v[1] = 9;
+        assert_eq!(v[1], 9);
+        assert_eq!(<[i32; 2]>::from(v), [7, 9]);
+        assert_eq!(v.components(), &[7, 9]);
+
+        let w = Vector3::new(1, 2, 3);
+        assert_eq!((w.x(), w.y(), w.z()), (1, 2, 3));
+        assert_eq!(w.to_string(), "(1, 2, 3)");
+        assert_eq!(Vector::<u8, 3>::default(), Vector::from([0, 0, 0]));
+        assert_eq!(w.map(|c| c * 10).into_array(), [10, 20, 30]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Display;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::parser_utils::parse_hex;
+
+/// A string of bits decoded from hex.
+///
+/// The bits are kept as `0`s and `1`s so that they can be fed straight into the parsers,
+/// which work on [`BitString::as_str`]. The other helpers are for looking at the bits
+/// around the packets, like the padding that follows a transmission.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitString {
+    bits: String,
+}
+
+/// A character that isn't a hex digit was found while decoding a [`BitString`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHexError {
+    /// The (0-based) position of the character in the original input, counted in characters.
+    pub position: usize,
+    pub found: char,
+}
+
+impl Display for ParseHexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid hex digit {:?} at position {}",
+            self.found, self.position
+        )
+    }
+}
+
+impl std::error::Error for ParseHexError {}
+
+impl FromStr for BitString {
+    type Err = ParseHexError;
+
+    /// Decode a hex string, ignoring any whitespace around it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let leading = s.len() - s.trim_start().len();
+
+        let (rest, nibbles) = parse_hex(trimmed).expect("parse_hex should never fail");
+        if let Some(found) = rest.chars().next() {
+            let offset = leading + trimmed.len() - rest.len();
+            return Err(ParseHexError {
+                position: s.char_indices().take_while(|&(i, _)| i < offset).count(),
+                found,
+            });
+        }
+
+        Ok(Self {
+            bits: nibbles.iter().map(|n| format!("{:04b}", n)).collect(),
+        })
+    }
+}
+
+impl Display for BitString {
+    /// Encode the bits back to hex, padding the last digit with zeros if needed.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for start in (0..self.len()).step_by(4) {
+            let end = (start + 4).min(self.len());
+            let nibble = self.read(start..end).unwrap() << (4 - (end - start));
+            write!(f, "{:X}", nibble)?;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<str> for BitString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl BitString {
+    /// Build a bit string from `0`s and `1`s, if that's all it contains.
+    pub fn from_bits(bits: &str) -> Option<Self> {
+        bits.chars().all(|c| c == '0' || c == '1').then(|| Self {
+            bits: bits.to_owned(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Get the bit at the given position.
+    pub fn bit(&self, index: usize) -> Option<bool> {
+        self.bits.as_bytes().get(index).map(|&b| b == b'1')
+    }
+
+    /// Get the bits in the given range.
+    pub fn slice(&self, range: Range<usize>) -> Option<&str> {
+        self.bits.get(range)
+    }
+
+    /// Read the bits in the given range as an unsigned number, most significant bit first.
+    pub fn read(&self, range: Range<usize>) -> Option<usize> {
+        let bits = self.slice(range)?;
+        if bits.is_empty() {
+            return Some(0);
+        }
+        usize::from_str_radix(bits, 2).ok()
+    }
+
+    /// Find the position of the first `1` in the given range.
+    pub fn find_one(&self, range: Range<usize>) -> Option<usize> {
+        let start = range.start;
+        self.slice(range)?.find('1').map(|i| start + i)
+    }
+
+    /// Returns true if every bit in the given range is a `0`, false otherwise,
+    /// including when the range reaches past the end of the string.
+    pub fn is_zero(&self, range: Range<usize>) -> bool {
+        self.slice(range).is_some_and(|bits| !bits.contains('1'))
+    }
+}
+
+/// Decode a packet from hex in tests, panicking if it isn't valid.
+#[cfg(test)]
+pub(crate) fn packet_from_hex(raw: &str) -> crate::packet::Packet {
+    use crate::packet::Parse;
+
+    let bits: BitString = raw.parse().unwrap();
+    crate::packet::Packet::parse(bits.as_str()).unwrap().1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("D2FE28", "110100101111111000101000")]
+    #[test_case(
+        "  38006F45291200\n",
+        "00111000000000000110111101000101001010010001001000000000"
+    )]
+    #[test_case("\r\n", "")]
+    #[test_case("a0f", "101000001111")]
+    fn from_hex(raw: &str, expected: &str) {
+        let bits: BitString = raw.parse().unwrap();
+        assert_eq!(bits.as_str(), expected);
+    }
+
+    #[test_case("D2FX28", 3, 'X')]
+    #[test_case("\n  D2FE28 x", 9, ' ')]
+    #[test_case("  D2 FE28", 4, ' ')]
+    #[test_case("0x1", 1, 'x')]
+    #[test_case("\u{3000}D2X", 3, 'X')]
+    #[test_case("D2é", 2, 'é')]
+    #[test_case("é", 0, 'é')]
+    fn invalid_hex(raw: &str, position: usize, found: char) {
+        assert_eq!(
+            raw.parse::<BitString>(),
+            Err(ParseHexError { position, found })
+        );
+    }
+
+    #[test_case("D2FE28", "D2FE28")]
+    #[test_case(" 9c01 ", "9C01")]
+    fn display(raw: &str, expected: &str) {
+        let bits: BitString = raw.parse().unwrap();
+        assert_eq!(bits.to_string(), expected);
+    }
+
+    #[test]
+    fn display_pads_last_digit() {
+        let bits = BitString::from_bits("110100101").unwrap();
+        assert_eq!(bits.to_string(), "D28");
+    }
+
+    #[test]
+    fn helpers() {
+        let bits: BitString = "D2FE28".parse().unwrap();
+        assert_eq!(bits.len(), 24);
+        assert_eq!(bits.bit(0), Some(true));
+        assert_eq!(bits.bit(2), Some(false));
+        assert_eq!(bits.bit(24), None);
+        assert_eq!(bits.read(0..3), Some(6));
+        assert_eq!(bits.read(3..6), Some(4));
+        assert_eq!(bits.read(20..30), None);
+        assert_eq!(bits.find_one(18..24), Some(18));
+        assert_eq!(bits.find_one(21..24), None);
+        assert!(bits.is_zero(21..24));
+        assert!(!bits.is_zero(0..24));
+        assert!(bits.is_zero(24..24));
+        assert_eq!(BitString::from_bits("10a"), None);
+    }
+
+    #[test_case(21..25)]
+    #[test_case(24..32)]
+    #[test_case(30..40)]
+    fn is_zero_past_the_end(range: Range<usize>) {
+        let bits: BitString = "D2FE28".parse().unwrap();
+        assert!(!bits.is_zero(range));
+    }
+}
@@ -0,0 +1,443 @@
+//! Serde support for packets.
+//!
+//! Operators are dumped along with their operation and computed value, so that the dump
+//! can be read without knowing the type ids. Everything is checked when loading a dump back,
+//! including those two fields, so that a loaded packet can always be evaluated without
+//! overflowing and encoded as BITS again.
+
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::packet::{EvalError, Header, Packet, PacketLiteral, PacketOperator};
+use crate::trace::Operator;
+use crate::visit::PacketVisitor;
+
+/// A packet as it's dumped, with the values of its operators worked out.
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum PacketDump {
+    Literal(PacketLiteral),
+    Operator(OperatorDump),
+}
+
+#[derive(Serialize)]
+struct OperatorDump {
+    header: Header,
+    operator: Operator,
+    length_type_id: u8,
+    subpackets: Vec<PacketDump>,
+    value: usize,
+}
+
+impl PacketDump {
+    fn value(&self) -> usize {
+        match self {
+            PacketDump::Literal(l) => l.value,
+            PacketDump::Operator(o) => o.value,
+        }
+    }
+}
+
+impl OperatorDump {
+    /// Dump an operator whose sub-packets have already been dumped,
+    /// if it can be evaluated.
+    fn new(operator: &PacketOperator, subpackets: Vec<PacketDump>) -> Result<Self, EvalError> {
+        let values = subpackets.iter().map(PacketDump::value).collect::<Vec<_>>();
+        let value = operator.apply(&values)?;
+        Ok(Self {
+            header: operator.header.clone(),
+            operator: operator.operator(),
+            length_type_id: operator.length_type_id,
+            subpackets,
+            value,
+        })
+    }
+}
+
+/// Dump a whole packet tree in a single pass, evaluating every operator once.
+struct Dump;
+
+impl PacketVisitor for Dump {
+    type Output = Result<PacketDump, EvalError>;
+
+    fn post_order(
+        &mut self,
+        packet: &Packet,
+        _path: &[usize],
+        subpackets: Vec<Result<PacketDump, EvalError>>,
+    ) -> Result<PacketDump, EvalError> {
+        match packet {
+            Packet::Literal(l) => Ok(PacketDump::Literal(l.clone())),
+            Packet::Operator(o) => {
+                OperatorDump::new(o, subpackets.into_iter().collect::<Result<_, _>>()?)
+                    .map(PacketDump::Operator)
+            }
+        }
+    }
+}
+
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fold(&mut Dump)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for PacketOperator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.subpackets
+            .iter()
+            .map(|packet| packet.fold(&mut Dump))
+            .collect::<Result<_, _>>()
+            .and_then(|subpackets| OperatorDump::new(self, subpackets))
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// A packet as it's loaded, before it's been checked.
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum RawPacket {
+    Literal(PacketLiteral),
+    Operator(RawOperator),
+}
+
+/// An operator as it's loaded. The operation and value are optional,
+/// but have to match the type id and sub-packets if they're given.
+#[derive(Deserialize)]
+struct RawOperator {
+    header: Header,
+    operator: Option<Operator>,
+    length_type_id: u8,
+    subpackets: Vec<RawPacket>,
+    value: Option<usize>,
+}
+
+/// The largest version a 3 bit header can hold.
+const MAX_VERSION: u8 = 0b111;
+/// The most sub-packets an operator with length type id 1 can count in 11 bits.
+const MAX_SUBPACKETS: usize = (1 << 11) - 1;
+/// The most bits of sub-packets an operator with length type id 0 can measure in 15 bits.
+const MAX_SUBPACKET_BITS: usize = (1 << 15) - 1;
+
+/// Check that the header fits in the 6 bits it's encoded in.
+///
+/// The type id always does once it's been checked against the kind of packet.
+fn check_header(header: &Header) -> Result<(), String> {
+    match header.version > MAX_VERSION {
+        true => Err(format!("version {} does not fit in 3 bits", header.version)),
+        false => Ok(()),
+    }
+}
+
+impl RawPacket {
+    /// Check the packet and everything in it, returning it along with its value
+    /// and the number of bits it takes up when it's encoded.
+    fn check(self) -> Result<(Packet, usize, usize), String> {
+        match self {
+            RawPacket::Literal(l) if l.header.type_id != 4 => Err(format!(
+                "a literal packet has type id {} instead of 4",
+                l.header.type_id
+            )),
+            RawPacket::Literal(l) => {
+                check_header(&l.header)?;
+                let value = l.value;
+                // Every group holds 4 bits of the value, and there's always at least one.
+                let groups = (usize::BITS - value.leading_zeros()).div_ceil(4).max(1) as usize;
+                Ok((Packet::Literal(l), value, 6 + 5 * groups))
+            }
+            RawPacket::Operator(o) => {
+                let (o, value, bits) = o.check()?;
+                Ok((Packet::Operator(o), value, bits))
+            }
+        }
+    }
+}
+
+impl RawOperator {
+    fn check(self) -> Result<(PacketOperator, usize, usize), String> {
+        check_header(&self.header)?;
+        let type_id = self.header.type_id;
+        let op = Operator::from_type_id(type_id)
+            .ok_or_else(|| format!("type id {} is not an operator", type_id))?;
+        if let Some(operator) = self.operator.filter(|&operator| operator != op) {
+            return Err(format!(
+                "type id {} is not a {} operator",
+                type_id, operator
+            ));
+        }
+        if self.length_type_id > 1 {
+            return Err(format!(
+                "length type id {} is not 0 or 1",
+                self.length_type_id
+            ));
+        }
+
+        let checked = self
+            .subpackets
+            .into_iter()
+            .map(RawPacket::check)
+            .collect::<Result<Vec<_>, _>>()?;
+        let subpacket_bits = checked.iter().map(|&(_, _, bits)| bits).sum::<usize>();
+        let (subpackets, values): (Vec<_>, Vec<_>) = checked
+            .into_iter()
+            .map(|(packet, value, _)| (packet, value))
+            .unzip();
+        if self.length_type_id == 1 && values.len() > MAX_SUBPACKETS {
+            return Err(format!(
+                "{} sub-packets do not fit in 11 bits",
+                values.len()
+            ));
+        }
+        if self.length_type_id == 0 && subpacket_bits > MAX_SUBPACKET_BITS {
+            return Err(format!(
+                "{} bits of sub-packets do not fit in 15 bits",
+                subpacket_bits
+            ));
+        }
+        if !op.accepts(values.len()) {
+            return Err(format!(
+                "a {} operator can't be applied to {} sub-packets",
+                op,
+                values.len()
+            ));
+        }
+
+        let operator = PacketOperator {
+            header: self.header,
+            length_type_id: self.length_type_id,
+            subpackets,
+        };
+        let value = operator
+            .apply(&values)
+            .map_err(|e| format!("a {} operator can't be evaluated: {}", op, e))?;
+        let length_bits = if self.length_type_id == 0 { 15 } else { 11 };
+        match self.value {
+            Some(expected) if expected != value => Err(format!(
+                "a {} operator has value {} instead of {}",
+                op, expected, value
+            )),
+            _ => Ok((operator, value, 7 + length_bits + subpacket_bits)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (packet, _, _) = RawPacket::deserialize(deserializer)?
+            .check()
+            .map_err(D::Error::custom)?;
+        Ok(packet)
+    }
+}
+
+impl<'de> Deserialize<'de> for PacketOperator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (operator, _, _) = RawOperator::deserialize(deserializer)?
+            .check()
+            .map_err(D::Error::custom)?;
+        Ok(operator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::packet_from_hex;
+    use serde_json::json;
+    use test_case::test_case;
+
+    fn literal(value: usize) -> serde_json::Value {
+        json!({ "kind": "literal", "header": { "version": 0, "type_id": 4 }, "value": value })
+    }
+
+    #[test]
+    fn loads_without_operator_and_value() {
+        let packet: Packet = serde_json::from_value(json!({
+            "kind": "operator",
+            "header": { "version": 1, "type_id": 1 },
+            "length_type_id": 1,
+            "subpackets": [literal(6), literal(7)],
+        }))
+        .unwrap();
+        assert_eq!(packet.value(), Ok(42));
+    }
+
+    #[test_case(json!({ "type_id": 4 }), json!(null), json!(1), "type id 4 is not an operator")]
+    #[test_case(json!({ "type_id": 9 }), json!(null), json!(1), "type id 9 is not an operator")]
+    #[test_case(json!({ "type_id": 0 }), json!("product"), json!(1), "type id 0 is not a product operator")]
+    #[test_case(json!({ "type_id": 0 }), json!("sum"), json!(3), "length type id 3 is not 0 or 1")]
+    #[test_case(json!({ "type_id": 0, "version": 8 }), json!(null), json!(1), "version 8 does not fit in 3 bits")]
+    #[test_case(json!({ "type_id": 9, "version": 255 }), json!(null), json!(1), "version 255 does not fit in 3 bits")]
+    fn rejects_bad_headers(
+        header: serde_json::Value,
+        operator: serde_json::Value,
+        length_type_id: serde_json::Value,
+        message: &str,
+    ) {
+        let mut header = header;
+        header
+            .as_object_mut()
+            .unwrap()
+            .entry("version")
+            .or_insert(json!(0));
+        let error = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": header,
+            "operator": operator,
+            "length_type_id": length_type_id,
+            "subpackets": [literal(1), literal(2)],
+        }))
+        .unwrap_err();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+
+    #[test_case(2, vec![], "a min operator can't be applied to 0 sub-packets")]
+    #[test_case(5, vec![literal(1)], "a gt operator can't be applied to 1 sub-packets")]
+    #[test_case(7, vec![literal(1), literal(1), literal(1)], "a eq operator can't be applied to 3 sub-packets")]
+    fn rejects_the_wrong_number_of_subpackets(
+        type_id: u8,
+        subpackets: Vec<serde_json::Value>,
+        message: &str,
+    ) {
+        let error = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": type_id },
+            "length_type_id": 1,
+            "subpackets": subpackets,
+        }))
+        .unwrap_err();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+
+    #[test_case(json!({ "version": 0, "type_id": 0 }), "a literal packet has type id 0 instead of 4")]
+    #[test_case(json!({ "version": 8, "type_id": 4 }), "version 8 does not fit in 3 bits")]
+    fn rejects_bad_literals(header: serde_json::Value, message: &str) {
+        let literal = json!({ "kind": "literal", "header": header, "value": 1 });
+        let error = serde_json::from_value::<Packet>(literal).unwrap_err();
+        assert!(error.to_string().starts_with(message), "{}", error);
+    }
+
+    // Every literal of 0 takes up 11 bits.
+    #[test_case(0, 2_978, None)]
+    #[test_case(0, 2_979, Some("32769 bits of sub-packets do not fit in 15 bits"))]
+    #[test_case(1, 2_047, None)]
+    #[test_case(1, 2_048, Some("2048 sub-packets do not fit in 11 bits"))]
+    fn rejects_subpackets_that_dont_fit_the_length(
+        length_type_id: u8,
+        count: usize,
+        message: Option<&str>,
+    ) {
+        let loaded = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 0 },
+            "length_type_id": length_type_id,
+            "subpackets": vec![literal(0); count],
+        }));
+        match message {
+            None => assert_eq!(loaded.unwrap().subpackets().len(), count),
+            Some(message) => {
+                let error = loaded.unwrap_err();
+                assert!(error.to_string().starts_with(message), "{}", error);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_the_bits_of_nested_subpackets() {
+        // 2^40 takes up 11 groups, so it's encoded in 61 bits and an operator around it in 79.
+        let inner = json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 0 },
+            "length_type_id": 1,
+            "subpackets": [literal(1 << 40)],
+        });
+        let fits = MAX_SUBPACKET_BITS / 79;
+        let loaded = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 0 },
+            "length_type_id": 0,
+            "subpackets": vec![inner.clone(); fits],
+        }));
+        assert!(loaded.is_ok());
+        let error = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 0 },
+            "length_type_id": 0,
+            "subpackets": vec![inner; fits + 1],
+        }))
+        .unwrap_err();
+        assert!(
+            error.to_string().starts_with(&format!(
+                "{} bits of sub-packets do not fit in 15 bits",
+                79 * (fits + 1)
+            )),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_overflow() {
+        let error = serde_json::from_value::<Packet>(json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 1 },
+            "length_type_id": 1,
+            "subpackets": [literal(1 << 40), literal(1 << 40)],
+        }))
+        .unwrap_err();
+        assert!(
+            error.to_string().starts_with(
+                "a product operator can't be evaluated: value does not fit in 64 bits"
+            ),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn refuses_to_dump_values_that_overflow() {
+        let packet = packet_from_hex("0600848C2108421084002461084210842000");
+        let error = serde_json::to_string(&packet).unwrap_err();
+        assert_eq!(error.to_string(), "value does not fit in 64 bits");
+    }
+
+    #[test]
+    fn rejects_nested_problems() {
+        let inner = json!({
+            "kind": "operator",
+            "header": { "version": 0, "type_id": 0 },
+            "length_type_id": 0,
+            "subpackets": [literal(1), literal(2)],
+            "value": 4,
+        });
+        let error = serde_json::from_value::<PacketOperator>(json!({
+            "header": { "version": 0, "type_id": 3 },
+            "length_type_id": 0,
+            "subpackets": [literal(1), inner],
+        }))
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("a sum operator has value 4 instead of 3"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn refuses_to_dump_operators_that_cant_be_applied() {
+        let operator = PacketOperator {
+            header: Header {
+                version: 0,
+                type_id: 2,
+            },
+            length_type_id: 1,
+            subpackets: vec![],
+        };
+        assert!(serde_json::to_string(&operator).is_err());
+        assert!(serde_json::to_string(&Packet::Operator(operator)).is_err());
+    }
+}
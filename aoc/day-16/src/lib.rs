@@ -1,35 +1,47 @@
 use aoc_core::Solution;
+mod bits;
+#[cfg(feature = "serde")]
+mod dump;
 mod packet;
 mod parser_utils;
+mod stream;
+mod trace;
+mod visit;
+pub use bits::*;
+pub use packet::*;
 pub use parser_utils::*;
-
-use crate::packet::{Packet, Parse};
+pub use stream::*;
+pub use trace::*;
+pub use visit::*;
 
 #[derive(Default)]
 pub struct Solver {}
 
+impl Solver {
+    /// Decode the one transmission in the puzzle input.
+    fn packet(&self) -> Packet {
+        let input = include_str!("input.txt");
+        TransmissionReader::new(input.as_bytes())
+            .next()
+            .expect("The input should contain a transmission.")
+            .unwrap_or_else(|e| panic!("Could not decode the input: {}", e))
+            .packet
+    }
+}
+
 impl Solution for Solver {
     fn part1(&self) -> String {
-        let input = include_str!("input.txt");
-        let bin = input
-            .chars()
-            .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-            .collect::<String>();
-        let (_, packet) = Packet::parse(&bin).unwrap();
-        let result = packet.sum_version_numbers();
+        let result = self.packet().sum_version_numbers();
         println!("Part 1: {:#?}", result);
         format!("{}", result)
     }
 
     fn part2(&self) -> String {
-        let input = include_str!("input.txt");
-        let bin = input
-            .chars()
-            .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-            .collect::<String>();
-        let (_, packet) = Packet::parse(&bin).unwrap();
-
-        format!("{}", packet.value())
+        let value = self
+            .packet()
+            .value()
+            .unwrap_or_else(|e| panic!("Could not evaluate the input: {}", e));
+        format!("{}", value)
     }
 }
 
@@ -7,6 +7,9 @@ use nom::{
     sequence::*,
     IResult,
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 /// Anything complex that can be parsed with parser combinators must implement this trait.
 pub trait Parse {
@@ -16,6 +19,7 @@ pub trait Parse {
 }
 
 #[derive(Debug, PartialEq, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Header {
     pub version: u8,
     pub type_id: u8,
@@ -37,36 +41,52 @@ impl Parse for Header {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PacketLiteral {
     pub header: Header,
     pub value: usize,
 }
 
-#[derive(Debug, Clone)]
+/// With the `serde` feature, every operator is dumped along with its operation and
+/// computed value, and packets are checked to be valid when they're loaded back.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Packet {
     Literal(PacketLiteral),
     Operator(PacketOperator),
 }
 
-impl Packet {
-    pub fn value(&self) -> usize {
+/// Something that stops a packet from being evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// A value doesn't fit in a `usize`.
+    Overflow,
+    /// An operator can't be applied to the given number of sub-packets,
+    /// or its type id isn't an operator at all.
+    InvalidOperator { type_id: u8, operands: usize },
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Packet::Literal(l) => l.value,
-            Packet::Operator(o) => o.value(),
+            EvalError::Overflow => write!(f, "value does not fit in {} bits", usize::BITS),
+            EvalError::InvalidOperator { type_id, operands } => write!(
+                f,
+                "type id {} can't be applied to {} sub-packets",
+                type_id, operands
+            ),
         }
     }
+}
+
+impl std::error::Error for EvalError {}
+
+impl Packet {
+    pub fn value(&self) -> Result<usize, EvalError> {
+        self.fold(&mut Evaluate)
+    }
 
     pub fn sum_version_numbers(&self) -> usize {
-        match self {
-            Packet::Literal(l) => l.header.version as usize,
-            Packet::Operator(o) => {
-                o.header.version as usize
-                    + o.subpackets
-                        .iter()
-                        .map(|c| c.sum_version_numbers())
-                        .sum::<usize>()
-            }
-        }
+        self.fold(&mut VersionSum)
     }
 }
 
@@ -103,13 +123,16 @@ impl Parse for PacketLiteral {
             },
         )(rest)?;
 
-        let value = usize::from_str_radix(&bits, 2).unwrap();
+        // Literals can have any number of groups, but only those that fit are any use to us.
+        let value = usize::from_str_radix(&bits, 2).map_err(|_| {
+            nom::Err::Error(nom::error::Error::new(s, nom::error::ErrorKind::TooLarge))
+        })?;
 
         Ok((rest, PacketLiteral { header, value }))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PacketOperator {
     pub header: Header,
     pub length_type_id: u8,
@@ -117,35 +140,46 @@ pub struct PacketOperator {
 }
 
 impl PacketOperator {
-    pub fn value(&self) -> usize {
-        let mut _iter = self.subpackets.iter().map(Packet::value);
-
-        match self.header.type_id {
-            // 0 => sum
-            0 => _iter.sum(),
-            // 1 => product
-            1 => _iter.product(),
-            // 2 => minimum,
-            2 => _iter.min().unwrap(),
-            // 3 => maximum,
-            3 => _iter.max().unwrap(),
-
-            5 | 6 | 7 => {
-                let first = _iter.next().unwrap();
-                let second = _iter.next().unwrap();
-
-                match self.header.type_id {
-                    // 5 => greater than,
-                    5 => usize::from(first > second),
-                    // 6 => less than,
-                    6 => usize::from(first < second),
-                    // 7 => equal,
-                    7 => usize::from(first == second),
-                    _ => panic!("Invalid length type id"),
-                }
-            }
+    pub fn value(&self) -> Result<usize, EvalError> {
+        let values = self
+            .subpackets
+            .iter()
+            .map(Packet::value)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.apply(&values)
+    }
 
-            _ => panic!("Invalid length type id"),
+    /// Apply this operator to the values of its sub-packets.
+    pub fn apply(&self, values: &[usize]) -> Result<usize, EvalError> {
+        let invalid = || EvalError::InvalidOperator {
+            type_id: self.header.type_id,
+            operands: values.len(),
+        };
+        let operator = Operator::from_type_id(self.header.type_id)
+            .filter(|operator| operator.accepts(values.len()))
+            .ok_or_else(invalid)?;
+        let mut iter = values.iter().copied();
+
+        match operator {
+            Operator::Sum => iter
+                .try_fold(0usize, usize::checked_add)
+                .ok_or(EvalError::Overflow),
+            Operator::Product => iter
+                .try_fold(1usize, usize::checked_mul)
+                .ok_or(EvalError::Overflow),
+            Operator::Minimum => iter.min().ok_or_else(invalid),
+            Operator::Maximum => iter.max().ok_or_else(invalid),
+            Operator::GreaterThan | Operator::LessThan | Operator::EqualTo => {
+                let [first, second] = values else {
+                    return Err(invalid());
+                };
+
+                Ok(usize::from(match operator {
+                    Operator::GreaterThan => first > second,
+                    Operator::LessThan => first < second,
+                    _ => first == second,
+                }))
+            }
         }
     }
 }
@@ -182,6 +216,16 @@ impl Parse for PacketOperator {
             }
         };
 
+        // Make sure the operator can be applied to as many sub-packets as it holds.
+        // Its value may still be too large to evaluate, which is left to the caller.
+        let operator = Operator::from_type_id(header.type_id);
+        if !operator.is_some_and(|operator| operator.accepts(child_packets.len())) {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                s,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+
         Ok((
             rest,
             PacketOperator {
@@ -213,11 +257,7 @@ mod tests {
     #[test_case("620080001611562C8802118E34", 12)]
     #[test_case("38006F45291200", 9)]
     fn parse_packet_from_hex(raw: &str, sum_version_number: usize) {
-        let bin = raw
-            .chars()
-            .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-            .collect::<String>();
-        let (_, packet) = Packet::parse(&bin).unwrap();
+        let packet = packet_from_hex(raw);
         assert_eq!(packet.sum_version_numbers(), sum_version_number);
     }
 
@@ -241,11 +281,71 @@ mod tests {
     #[test_case("9C005AC2F8F0", 0)]
     #[test_case("9C0141080250320F1802104A08", 1)]
     fn value(raw: &str, expected_value: usize) {
-        let bin = raw
-            .chars()
-            .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-            .collect::<String>();
-        let (_, packet) = Packet::parse(&bin).unwrap();
-        assert_eq!(packet.value(), expected_value);
+        let packet = packet_from_hex(raw);
+        assert_eq!(packet.value(), Ok(expected_value));
+    }
+
+    // The product of two literals of 2^40.
+    #[test_case("0600848C2108421084002461084210842000")]
+    fn value_overflows(raw: &str) {
+        let packet = packet_from_hex(raw);
+        assert_eq!(packet.value(), Err(EvalError::Overflow));
+    }
+
+    #[test_case(0, &[usize::MAX, 1], Err(EvalError::Overflow))]
+    #[test_case(0, &[usize::MAX, 0], Ok(usize::MAX))]
+    #[test_case(0, &[], Ok(0))]
+    #[test_case(1, &[], Ok(1))]
+    #[test_case(1, &[usize::MAX, 0], Ok(0))]
+    #[test_case(2, &[], Err(EvalError::InvalidOperator { type_id: 2, operands: 0 }))]
+    #[test_case(3, &[], Err(EvalError::InvalidOperator { type_id: 3, operands: 0 }))]
+    #[test_case(4, &[1], Err(EvalError::InvalidOperator { type_id: 4, operands: 1 }))]
+    #[test_case(5, &[2, 1], Ok(1))]
+    #[test_case(6, &[2], Err(EvalError::InvalidOperator { type_id: 6, operands: 1 }))]
+    #[test_case(7, &[1, 1, 1], Err(EvalError::InvalidOperator { type_id: 7, operands: 3 }))]
+    fn apply(type_id: u8, values: &[usize], expected: Result<usize, EvalError>) {
+        let operator = PacketOperator {
+            header: Header {
+                version: 0,
+                type_id,
+            },
+            length_type_id: 1,
+            subpackets: vec![],
+        };
+        assert_eq!(operator.apply(values), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test_case("D2FE28")]
+    #[test_case("38006F45291200")]
+    #[test_case("9C0141080250320F1802104A08")]
+    #[test_case("A0016C880162017C3686B18A3D4780")]
+    fn serde_round_trip(raw: &str) {
+        let packet = packet_from_hex(raw);
+        let json = serde_json::to_string(&packet).unwrap();
+        let loaded: Packet = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, packet);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_shape() {
+        let (_, packet) =
+            Packet::parse("00111000000000000110111101000101001010010001001000000000").unwrap();
+        let json = serde_json::to_value(&packet).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "operator",
+                "header": { "version": 1, "type_id": 6 },
+                "operator": "less_than",
+                "length_type_id": 0,
+                "subpackets": [
+                    { "kind": "literal", "header": { "version": 6, "type_id": 4 }, "value": 10 },
+                    { "kind": "literal", "header": { "version": 2, "type_id": 4 }, "value": 20 },
+                ],
+                "value": 1,
+            })
+        );
     }
 }
@@ -16,11 +16,13 @@ pub fn is_one(c: char) -> bool {
     c == '1'
 }
 
+/// Take the leading hex digits of the input as a list of nibbles.
 pub fn parse_hex(s: &str) -> IResult<&str, Vec<u8>> {
     map(take_while(is_hex_digit), |hex_str: &str| {
         hex_str
             .chars()
-            .map(|c: char| u8::from_str_radix(c.to_string().as_str(), 16).unwrap())
+            .filter_map(|c: char| c.to_digit(16))
+            .map(|d| d as u8)
             .collect::<Vec<u8>>()
     })(s)
 }
@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Lines, Read};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::bits::BitString;
+use crate::packet::{Packet, Parse};
+
+/// A single outermost packet decoded from a stream of transmissions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transmission {
+    /// The line (1-based) of the input the transmission was read from.
+    pub line: usize,
+    /// The offset (in bits) into the line at which the packet starts.
+    pub offset: usize,
+    /// The decoded packet.
+    pub packet: Packet,
+    /// The number of zero bits of padding that followed the packet.
+    pub padding: usize,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// A character that is not a hex digit was found at the given (0-based) column.
+    InvalidHexDigit {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// No packet could be parsed starting at the given bit offset.
+    Malformed { line: usize, offset: usize },
+    /// The padding following a packet contains a `1` at the given bit offset.
+    NonZeroPadding { line: usize, offset: usize },
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(e) => write!(f, "could not read transmission: {}", e),
+            DecodeError::InvalidHexDigit {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "line {}, column {}: invalid hex digit {:?}",
+                line, column, found
+            ),
+            DecodeError::Malformed { line, offset } => {
+                write!(f, "line {}, bit {}: malformed packet", line, offset)
+            }
+            DecodeError::NonZeroPadding { line, offset } => {
+                write!(f, "line {}, bit {}: padding is not all zeros", line, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Io(e)
+    }
+}
+
+/// Decode BITS transmissions from any reader.
+///
+/// Every non-empty line is hex encoded and may contain one or more transmissions
+/// written back to back. A transmission is padded with zeros up to the next byte
+/// boundary, and whatever is left at the end of a line must be all zeros as well.
+///
+/// If a line can't be decoded, an error is yielded and the rest of that line is skipped.
+pub struct TransmissionReader<R> {
+    lines: Lines<BufReader<R>>,
+    line: usize,
+    pending: VecDeque<Result<Transmission, DecodeError>>,
+}
+
+impl<R: Read> TransmissionReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            lines: BufReader::new(input).lines(),
+            line: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for TransmissionReader<R> {
+    type Item = Result<Transmission, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Some(result);
+            }
+            match self.lines.next()? {
+                Ok(hex) => {
+                    self.line += 1;
+                    self.pending.extend(decode_line(self.line, hex.trim()));
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+    }
+}
+
+/// Decode all the transmissions written back to back on a single line.
+fn decode_line(line: usize, hex: &str) -> Vec<Result<Transmission, DecodeError>> {
+    let bits = match hex.parse::<BitString>() {
+        Ok(bits) => bits,
+        Err(e) => {
+            return vec![Err(DecodeError::InvalidHexDigit {
+                line,
+                column: e.position,
+                found: e.found,
+            })]
+        }
+    };
+
+    let mut results = vec![];
+    let mut offset = 0;
+
+    // Whatever remains after the last packet must be padding, and a
+    // run of zeros can never be a valid packet by itself.
+    while !bits.is_zero(offset..bits.len()) {
+        let rest = match Packet::parse(&bits.as_str()[offset..]) {
+            Ok((rest, packet)) => {
+                results.push(Ok(Transmission {
+                    line,
+                    offset,
+                    packet,
+                    padding: 0,
+                }));
+                rest
+            }
+            Err(_) => {
+                results.push(Err(DecodeError::Malformed { line, offset }));
+                return results;
+            }
+        };
+
+        let end = bits.len() - rest.len();
+        let aligned = (end.div_ceil(8) * 8).min(bits.len());
+        if let Some(one) = bits.find_one(end..aligned) {
+            results.push(Err(DecodeError::NonZeroPadding { line, offset: one }));
+            return results;
+        }
+        offset = aligned;
+
+        if let Some(Ok(transmission)) = results.last_mut() {
+            transmission.padding = if bits.is_zero(offset..bits.len()) {
+                bits.len() - end
+            } else {
+                offset - end
+            };
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn decode(raw: &str) -> Vec<Result<Transmission, DecodeError>> {
+        TransmissionReader::new(raw.as_bytes()).collect()
+    }
+
+    #[test_case("D2FE28", 2021, 3)]
+    #[test_case("38006F45291200", 1, 7)]
+    #[test_case("EE00D40C823060", 3, 5)]
+    fn single_transmission(raw: &str, value: usize, padding: usize) {
+        let results = decode(raw);
+        assert_eq!(results.len(), 1);
+        let transmission = results[0].as_ref().unwrap();
+        assert_eq!(transmission.line, 1);
+        assert_eq!(transmission.offset, 0);
+        assert_eq!(transmission.packet.value(), Ok(value));
+        assert_eq!(transmission.padding, padding);
+    }
+
+    #[test]
+    fn one_per_line() {
+        let results = decode("C200B40A82\n\n04005AC33890\r\n880086C3E88112\n");
+        let values = results
+            .iter()
+            .map(|r| {
+                let t = r.as_ref().unwrap();
+                (t.line, t.packet.value().unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![(1, 3), (3, 54), (4, 7)]);
+    }
+
+    #[test]
+    fn concatenated() {
+        let results = decode("D2FE2838006F45291200C200B40A82");
+        let transmissions = results
+            .into_iter()
+            .map(Result::unwrap)
+            .map(|t| (t.offset, t.packet.value().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(transmissions, vec![(0, 2021), (24, 1), (80, 3)]);
+    }
+
+    #[test]
+    fn non_zero_padding() {
+        let results = decode("D2FE29");
+        assert!(matches!(
+            results[..],
+            [
+                Ok(_),
+                Err(DecodeError::NonZeroPadding {
+                    line: 1,
+                    offset: 23
+                })
+            ]
+        ));
+    }
+
+    #[test]
+    fn invalid_hex_digit() {
+        let results = decode("D2FE28\nD2XE28\n38006F45291200");
+        assert!(matches!(
+            results[..],
+            [
+                Ok(_),
+                Err(DecodeError::InvalidHexDigit {
+                    line: 2,
+                    column: 2,
+                    found: 'X'
+                }),
+                Ok(_)
+            ]
+        ));
+    }
+
+    // A literal wider than 64 bits.
+    #[test_case("13FFFFFFFFFFFFFFFFFFFDE0")]
+    // A minimum of no sub-packets.
+    #[test_case("0A0000")]
+    // A comparison of a single sub-packet.
+    #[test_case("16004408")]
+    #[test_case("38006F4529")]
+    fn malformed(raw: &str) {
+        let results = decode(raw);
+        assert!(matches!(
+            results[..],
+            [Err(DecodeError::Malformed { line: 1, offset: 0 })]
+        ));
+    }
+
+    #[test]
+    fn long_literal_with_leading_zeros() {
+        let results = decode("1210842108421084210804");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap().packet.value(), Ok(1));
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::packet::{EvalError, Packet, PacketOperator};
+use crate::visit::PacketVisitor;
+
+/// The operation an operator packet performs on the values of its sub-packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Operator {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl Operator {
+    /// Get the operator for the given packet type id, if it is one.
+    pub fn from_type_id(type_id: u8) -> Option<Self> {
+        match type_id {
+            0 => Some(Operator::Sum),
+            1 => Some(Operator::Product),
+            2 => Some(Operator::Minimum),
+            3 => Some(Operator::Maximum),
+            5 => Some(Operator::GreaterThan),
+            6 => Some(Operator::LessThan),
+            7 => Some(Operator::EqualTo),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the operator can be applied to the given number of operands.
+    pub fn accepts(&self, operands: usize) -> bool {
+        match self {
+            Operator::Sum | Operator::Product => true,
+            Operator::Minimum | Operator::Maximum => operands > 0,
+            Operator::GreaterThan | Operator::LessThan | Operator::EqualTo => operands == 2,
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => "gt",
+            Operator::LessThan => "lt",
+            Operator::EqualTo => "eq",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl PacketOperator {
+    pub fn operator(&self) -> Operator {
+        Operator::from_type_id(self.header.type_id).expect("Invalid operator type id")
+    }
+}
+
+/// A single operator application recorded while evaluating a packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Step {
+    /// The sub-packet indices leading from the outermost packet to the operator.
+    pub path: Vec<usize>,
+    pub version: u8,
+    pub operator: Operator,
+    /// The values of the operator's sub-packets, in order.
+    pub operands: Vec<usize>,
+    pub result: usize,
+}
+
+/// Every operator application made while evaluating a packet, in the order they were made.
+///
+/// Sub-expressions are always applied before the expression containing them,
+/// so the outermost operator (if any) is the last step.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub value: usize,
+}
+
+impl Display for Trace {
+    /// Print one step per line, indented by how deep the operator is nested.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            let path = match step.path.is_empty() {
+                true => "root".to_owned(),
+                false => step
+                    .path
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join("."),
+            };
+            let operands = step
+                .operands
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                f,
+                "{:indent$}{} (v{}): {}({}) = {}",
+                "",
+                path,
+                step.version,
+                step.operator,
+                operands,
+                step.result,
+                indent = 2 * step.path.len()
+            )?;
+        }
+        write!(f, "value = {}", self.value)
+    }
+}
+
+/// Evaluate the packet tree like [`crate::Evaluate`] does, while recording every operator application.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    pub steps: Vec<Step>,
+}
+
+impl PacketVisitor for Tracer {
+    type Output = Result<usize, EvalError>;
+
+    fn post_order(
+        &mut self,
+        packet: &Packet,
+        path: &[usize],
+        subpackets: Vec<Result<usize, EvalError>>,
+    ) -> Result<usize, EvalError> {
+        match packet {
+            Packet::Literal(l) => Ok(l.value),
+            Packet::Operator(o) => {
+                let operands = subpackets.into_iter().collect::<Result<Vec<_>, _>>()?;
+                let result = o.apply(&operands)?;
+                self.steps.push(Step {
+                    path: path.to_vec(),
+                    version: o.header.version,
+                    operator: o.operator(),
+                    operands,
+                    result,
+                });
+                Ok(result)
+            }
+        }
+    }
+}
+
+impl Packet {
+    /// Compute the value of the packet, recording every operator application on the way.
+    pub fn evaluate_with_trace(&self) -> Result<Trace, EvalError> {
+        let mut tracer = Tracer::default();
+        let value = self.fold(&mut tracer)?;
+        Ok(Trace {
+            steps: tracer.steps,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::packet_from_hex;
+    use test_case::test_case;
+
+    #[test]
+    fn steps() {
+        let trace = packet_from_hex("9C0141080250320F1802104A08")
+            .evaluate_with_trace()
+            .unwrap();
+        assert_eq!(trace.value, 1);
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|s| (s.path.clone(), s.operator, s.operands.clone(), s.result))
+                .collect::<Vec<_>>(),
+            vec![
+                (vec![0], Operator::Sum, vec![1, 3], 4),
+                (vec![1], Operator::Product, vec![2, 2], 4),
+                (vec![], Operator::EqualTo, vec![4, 4], 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let trace = packet_from_hex("9C0141080250320F1802104A08")
+            .evaluate_with_trace()
+            .unwrap();
+        assert_eq!(
+            trace.to_string(),
+            "  0 (v2): sum(1, 3) = 4
+  1 (v6): product(2, 2) = 4
+root (v4): eq(4, 4) = 1
+value = 1"
+        );
+    }
+
+    #[test_case("C200B40A82", 3)]
+    #[test_case("04005AC33890", 54)]
+    #[test_case("880086C3E88112", 7)]
+    #[test_case("CE00C43D881120", 9)]
+    #[test_case("D8005AC2A8F0", 1)]
+    #[test_case("F600BC2D8F", 0)]
+    #[test_case("9C005AC2F8F0", 0)]
+    fn agrees_with_value(raw: &str, expected: usize) {
+        let packet = packet_from_hex(raw);
+        let trace = packet.evaluate_with_trace().unwrap();
+        assert_eq!(trace.value, expected);
+        assert_eq!(Ok(trace.steps.last().unwrap().result), packet.value());
+    }
+
+    #[test]
+    fn overflow() {
+        let packet = packet_from_hex("0600848C2108421084002461084210842000");
+        assert_eq!(packet.evaluate_with_trace(), Err(EvalError::Overflow));
+    }
+
+    #[test]
+    fn literal_has_no_steps() {
+        let trace = packet_from_hex("D2FE28").evaluate_with_trace().unwrap();
+        assert!(trace.steps.is_empty());
+        assert_eq!(trace.value, 2021);
+    }
+}
@@ -0,0 +1,227 @@
+use crate::packet::{EvalError, Header, Packet};
+
+/// Anything that wants to compute something over a whole packet tree must implement this trait.
+///
+/// The `path` handed to the hooks is the list of sub-packet indices that lead
+/// from the outermost packet to the packet being visited, so the outermost packet
+/// has an empty path and its second sub-packet has the path `[1]`.
+pub trait PacketVisitor {
+    type Output;
+
+    /// Called when a packet is reached, before any of its sub-packets are visited.
+    fn pre_order(&mut self, _packet: &Packet, _path: &[usize]) {}
+
+    /// Called after all the sub-packets of a packet have been visited, with their outputs in order.
+    fn post_order(
+        &mut self,
+        packet: &Packet,
+        path: &[usize],
+        subpackets: Vec<Self::Output>,
+    ) -> Self::Output;
+}
+
+impl Packet {
+    pub fn header(&self) -> &Header {
+        match self {
+            Packet::Literal(l) => &l.header,
+            Packet::Operator(o) => &o.header,
+        }
+    }
+
+    pub fn subpackets(&self) -> &[Packet] {
+        match self {
+            Packet::Literal(_) => &[],
+            Packet::Operator(o) => &o.subpackets,
+        }
+    }
+
+    /// Walk the packet tree depth-first, calling the visitor's hooks for every packet.
+    ///
+    /// This keeps its own stack instead of recursing, so arbitrarily deep
+    /// transmissions can't overflow the call stack.
+    pub fn fold<V: PacketVisitor>(&self, visitor: &mut V) -> V::Output {
+        let mut path: Vec<usize> = vec![];
+        // Every frame holds a packet along with the outputs of its sub-packets visited so far.
+        let mut stack: Vec<(&Packet, Vec<V::Output>)> = vec![(self, vec![])];
+        visitor.pre_order(self, &path);
+
+        loop {
+            let (packet, outputs) = stack.last().unwrap();
+            let visited = outputs.len();
+
+            if let Some(child) = packet.subpackets().get(visited) {
+                path.push(visited);
+                visitor.pre_order(child, &path);
+                stack.push((child, vec![]));
+                continue;
+            }
+
+            let (packet, outputs) = stack.pop().unwrap();
+            let output = visitor.post_order(packet, &path, outputs);
+            match stack.last_mut() {
+                Some((_, parent_outputs)) => {
+                    parent_outputs.push(output);
+                    path.pop();
+                }
+                None => return output,
+            }
+        }
+    }
+}
+
+/// Sum up the version numbers of every packet in the tree.
+#[derive(Debug, Default)]
+pub struct VersionSum;
+
+impl PacketVisitor for VersionSum {
+    type Output = usize;
+
+    fn post_order(&mut self, packet: &Packet, _path: &[usize], subpackets: Vec<usize>) -> usize {
+        packet.header().version as usize + subpackets.iter().sum::<usize>()
+    }
+}
+
+/// Compute the value of the expression the packet tree represents.
+#[derive(Debug, Default)]
+pub struct Evaluate;
+
+impl PacketVisitor for Evaluate {
+    type Output = Result<usize, EvalError>;
+
+    fn post_order(
+        &mut self,
+        packet: &Packet,
+        _path: &[usize],
+        subpackets: Vec<Result<usize, EvalError>>,
+    ) -> Result<usize, EvalError> {
+        match packet {
+            Packet::Literal(l) => Ok(l.value),
+            Packet::Operator(o) => o.apply(&subpackets.into_iter().collect::<Result<Vec<_>, _>>()?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::packet_from_hex;
+    use crate::packet::{PacketLiteral, PacketOperator};
+    use std::collections::BTreeMap;
+
+    /// Record the paths in the order the hooks see them.
+    #[derive(Default)]
+    struct Order {
+        pre: Vec<Vec<usize>>,
+        post: Vec<Vec<usize>>,
+    }
+
+    impl PacketVisitor for Order {
+        type Output = ();
+
+        fn pre_order(&mut self, _packet: &Packet, path: &[usize]) {
+            self.pre.push(path.to_vec());
+        }
+
+        fn post_order(&mut self, _packet: &Packet, path: &[usize], _subpackets: Vec<()>) {
+            self.post.push(path.to_vec());
+        }
+    }
+
+    /// Count the packets of every type id.
+    #[derive(Default)]
+    struct CountByType(BTreeMap<u8, usize>);
+
+    impl PacketVisitor for CountByType {
+        type Output = ();
+
+        fn pre_order(&mut self, packet: &Packet, _path: &[usize]) {
+            *self.0.entry(packet.header().type_id).or_default() += 1;
+        }
+
+        fn post_order(&mut self, _packet: &Packet, _path: &[usize], _subpackets: Vec<()>) {}
+    }
+
+    /// The depth of the deepest packet.
+    struct Depth;
+
+    impl PacketVisitor for Depth {
+        type Output = usize;
+
+        fn post_order(
+            &mut self,
+            _packet: &Packet,
+            _path: &[usize],
+            subpackets: Vec<usize>,
+        ) -> usize {
+            1 + subpackets.into_iter().max().unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn paths() {
+        // `1 + 3 = 2 * 2`, i.e. an operator over two operators with two literals each.
+        let packet = packet_from_hex("9C0141080250320F1802104A08");
+        let mut order = Order::default();
+        packet.fold(&mut order);
+        assert_eq!(
+            order.pre,
+            vec![
+                vec![],
+                vec![0],
+                vec![0, 0],
+                vec![0, 1],
+                vec![1],
+                vec![1, 0],
+                vec![1, 1]
+            ]
+        );
+        assert_eq!(
+            order.post,
+            vec![
+                vec![0, 0],
+                vec![0, 1],
+                vec![0],
+                vec![1, 0],
+                vec![1, 1],
+                vec![1],
+                vec![]
+            ]
+        );
+    }
+
+    #[test]
+    fn count_by_type() {
+        let packet = packet_from_hex("9C0141080250320F1802104A08");
+        let mut counts = CountByType::default();
+        packet.fold(&mut counts);
+        assert_eq!(
+            counts.0.into_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (4, 4), (7, 1)]
+        );
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let header = Header {
+            version: 1,
+            type_id: 0,
+        };
+        let mut packet = Packet::Literal(PacketLiteral {
+            header: Header {
+                version: 1,
+                type_id: 4,
+            },
+            value: 7,
+        });
+        for _ in 0..5_000 {
+            packet = Packet::Operator(PacketOperator {
+                header: header.clone(),
+                length_type_id: 1,
+                subpackets: vec![packet],
+            });
+        }
+        assert_eq!(packet.fold(&mut Depth), 5_001);
+        assert_eq!(packet.value(), Ok(7));
+        assert_eq!(packet.sum_version_numbers(), 5_001);
+    }
+}
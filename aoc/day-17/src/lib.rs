@@ -1,56 +1,33 @@
-use std::{ops::{Range, Add, AddAssign, Neg}};
-
-use aoc_core::Solution;
+mod parse;
+mod physics;
+mod search;
+mod svg;
+
+use std::{fmt::Display, ops::{RangeInclusive, AddAssign, Neg}};
+
+use aoc_core::{Solution, Vector2};
+pub use parse::*;
+pub use physics::*;
+pub use search::*;
+pub use svg::*;
 use num_traits::Num;
-use rayon::prelude::*;
-
-
-#[derive(Copy, PartialEq, Eq, Debug)]
-pub struct Vector<T>(T, T);
-
-
-impl<T> Clone for Vector<T> where T: Clone {
-    fn clone(&self) -> Self {
-        Self(self.0.clone(), self.1.clone())
-    }
-}
-
-impl<T> Vector<T> {
-    pub fn new(x: T, y: T) -> Self {
-        Self(x, y)
-    }
-}
-
-impl<T> Add for Vector<T> where T: Add<Output = T> {
-    type Output = Vector<T>;
-    fn add(self, other: Vector<T>) -> Self {
-        Self::new(self.0 + other.0, self.1 + other.1)
-    }
-}
-
-
-impl<T> AddAssign for Vector<T> where T: AddAssign<T> {
-    fn add_assign(&mut self, other: Vector<T>) {
-        self.0 += other.0;
-        self.1 += other.1;
-    }
-}
 
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct GameState<T> {
-    pub target_area: (Range<T>, Range<T>),
-    pub initial_position: Vector<T>,
-    pub initial_velocities: Vector<T>,
-    pub position: Vector<T>,
-    pub velocity: Vector<T>,
+pub struct GameState<T, P = PuzzlePhysics> {
+    pub target_area: (RangeInclusive<T>, RangeInclusive<T>),
+    pub initial_position: Vector2<T>,
+    pub initial_velocities: Vector2<T>,
+    pub position: Vector2<T>,
+    pub velocity: Vector2<T>,
     pub largest_height: T,
+    pub physics: P,
 }
 
 impl<T: Number> GameState<T> {
     pub fn new(
-        x_range: Range<T>, 
-        y_range: Range<T>,
+        x_range: RangeInclusive<T>, 
+        y_range: RangeInclusive<T>,
         initial_velocity_x: T,
         initial_velocity_y: T,
         initial_position_x: T,
@@ -58,54 +35,121 @@ impl<T: Number> GameState<T> {
     ) -> Self {
         Self {
             target_area: (x_range, y_range),
-            initial_position: Vector::new(initial_position_x, initial_position_y),
-            initial_velocities: Vector::new(initial_velocity_x, initial_velocity_y),
-            position: Vector::new(initial_position_x, initial_position_y),
-            velocity: Vector::new(initial_velocity_x, initial_velocity_y),
+            initial_position: Vector2::new(initial_position_x, initial_position_y),
+            initial_velocities: Vector2::new(initial_velocity_x, initial_velocity_y),
+            position: Vector2::new(initial_position_x, initial_position_y),
+            velocity: Vector2::new(initial_velocity_x, initial_velocity_y),
             largest_height: initial_position_y,
+            physics: PuzzlePhysics,
+        }
+    }
+}
+
+impl<T: Number, P: Physics<T>> GameState<T, P> {
+    /// Launch a probe under the given physics, from wherever it says to launch from.
+    pub fn with_physics(
+        x_range: RangeInclusive<T>,
+        y_range: RangeInclusive<T>,
+        initial_velocity_x: T,
+        initial_velocity_y: T,
+        physics: P,
+    ) -> Self {
+        let initial_position = physics.initial_position();
+        Self {
+            target_area: (x_range, y_range),
+            initial_position,
+            initial_velocities: Vector2::new(initial_velocity_x, initial_velocity_y),
+            position: initial_position,
+            velocity: Vector2::new(initial_velocity_x, initial_velocity_y),
+            largest_height: initial_position.y(),
+            physics,
         }
     }
 
     pub fn is_in_target_area(&self) -> bool {
-        self.target_area.0.contains(&self.position.0) && self.target_area.1.contains(&self.position.1)
+        self.target_area.0.contains(&self.position.x()) && self.target_area.1.contains(&self.position.y())
     }
 
-    pub fn completes_in_num_steps(&mut self, num_steps: usize) -> bool {
-        for _ in 0..num_steps {
-            self.step();
-            if self.is_in_target_area() {
-                return true;
+    /// Decide how the shot ends if that's already certain, given
+    /// the number of steps taken so far.
+    pub fn outcome(&self, step: usize) -> Option<Outcome> {
+        if self.is_in_target_area() {
+            return Some(Outcome::Hit { step });
+        }
+
+        self.physics.miss(self.position, self.velocity, &self.target_area)
+    }
+
+    /// Keep stepping until it's certain how the shot ends.
+    pub fn run(&mut self) -> Outcome {
+        let mut step = 0;
+        loop {
+            if let Some(outcome) = self.outcome(step) {
+                return outcome;
             }
+            self.step();
+            step += 1;
         }
-        false
     }
 }
 
-pub struct Trajectory<T> {
-    pub game_state: GameState<T>
+/// How a shot ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The probe is inside the target area after the given number of steps.
+    Hit { step: usize },
+    /// The probe is past the target area sideways and still moving away from it.
+    Overshot,
+    /// The probe is below the target area and still falling.
+    FellBelow,
+    /// The probe is above the target area and still rising, which takes gravity that doesn't pull it down.
+    FlewAbove,
+    /// The probe stopped moving along an axis before it lined up with the target area.
+    Stalled,
 }
 
-impl<T: Number> Trajectory<T> {
-    pub fn new(initial: GameState<T>) -> Self {
-        Self { game_state: initial }
+/// The positions a probe goes through, ending as soon as it's
+/// in the target area or can never get there.
+pub struct Trajectory<T, P = PuzzlePhysics> {
+    pub game_state: GameState<T, P>,
+    steps: usize,
+    outcome: Option<Outcome>,
+}
+
+impl<T: Number, P: Physics<T>> Trajectory<T, P> {
+    pub fn new(initial: GameState<T, P>) -> Self {
+        let outcome = initial.outcome(0);
+        Self { game_state: initial, steps: 0, outcome }
+    }
+
+    /// How the shot ended, once the trajectory is exhausted.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// The number of steps taken so far.
+    pub fn steps(&self) -> usize {
+        self.steps
     }
 }
 
-impl<T: Number> Iterator for Trajectory<T> {
-    type Item = Vector<T>;
+impl<T: Number, P: Physics<T>> Iterator for Trajectory<T, P> {
+    type Item = Vector2<T>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.game_state.is_in_target_area() {
+        if self.outcome.is_some() {
             return None;
         }
         self.game_state.step();
+        self.steps += 1;
+        self.outcome = self.game_state.outcome(self.steps);
         Some(self.game_state.position)
     }
 }
 
-impl<T: Number> IntoIterator for GameState<T> {
-    type Item = Vector<T>;
+impl<T: Number, P: Physics<T>> IntoIterator for GameState<T, P> {
+    type Item = Vector2<T>;
 
-    type IntoIter = Trajectory<T>;
+    type IntoIter = Trajectory<T, P>;
 
     fn into_iter(self) -> Self::IntoIter {
         Trajectory::new(self)
@@ -127,94 +171,159 @@ pub trait Number: Num + Clone + AddAssign + Copy + Neg<Output=Self> + PartialOrd
 }
 impl<T> Number for T where T: Num + Clone + AddAssign + Copy + Neg<Output=T> + PartialOrd + Ord {}
 
-impl<T: Number> GameState<T> {
+impl<T: Number, P: Physics<T>> GameState<T, P> {
     pub fn step(&mut self) {
-        self.position += self.velocity.clone();
-        self.velocity += Vector::new(T::signum(self.velocity.0).neg(), T::one().neg());
-        self.largest_height = self.largest_height.max(self.position.1);
+        self.position += self.velocity + self.physics.wind();
+        self.velocity += self.physics.drag(self.velocity) + self.physics.gravity();
+        self.largest_height = self.largest_height.max(self.position.y());
     }
 }
 
+/// The puzzle input.
+pub const INPUT: &str = include_str!("input.txt");
+
 #[derive(Default)]
 pub struct Solver {}
 
 impl Solution for Solver {
     fn part1(&self) -> String {
-        solve_part1()
+        solve_part1(INPUT).unwrap_or_else(|e| panic!("Could not solve the puzzle input: {}", e))
     }
     fn part2(&self) -> String {
-        solve_part2()
-    }
-}
-
-
-pub fn solve_part1() -> String {
-    let x_min = 0;
-    let x_max = 1000;
-    let y_min = 0;
-    let y_max = 1000;
-    let max_steps = 1000;
-
-    let best = 
-    (x_min..x_max).flat_map(|x| (y_min..y_max).clone().map(move |y| (x, y)))
-    .collect::<Vec<(i32, i32)>>()
-    // 24-core CPU goes brrrrr.
-    .par_iter()
-    .map(|&(ivx, ivy)| {
-        let mut game_state = GameState::new(
-            230..284, 
-            -107..-56, 
-            ivx, 
-            ivy,
-            0, 
-            0
-        );
-        if game_state.completes_in_num_steps(max_steps) {
-            game_state.largest_height
-        } else {
-            0
+        solve_part2(INPUT).unwrap_or_else(|e| panic!("Could not solve the puzzle input: {}", e))
+    }
+}
+
+/// Why an input couldn't be solved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// The input doesn't describe a target area, going wrong at the given byte.
+    Parse { position: usize },
+    /// The hitting shots couldn't be listed.
+    Search(SearchError),
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Parse { position } => write!(
+                f,
+                "byte {}: expected a target area like `target area: x=20..30, y=-10..-5`",
+                position
+            ),
+            SolveError::Search(e) => write!(f, "{}", e),
         }
-    })
-    .max()
-    .unwrap();
-    format!("{}", best)
-}
-
-
-pub fn solve_part2() -> String {
-    // Some arbitrary range.
-    let x_min = -1000;
-    let x_max = 1000;
-    let y_min = -1000;
-    let y_max = 1000;
-    let max_steps = 2_000;
-
-    let best = 
-    (x_min..x_max).flat_map(|x| (y_min..y_max).clone().map(move |y| (x, y)))
-    .collect::<Vec<(i32, i32)>>()
-    // 24-core CPU goes brrrrr.
-    .par_iter()
-    .map(|&(ivx, ivy)| {
-        let mut game_state = GameState::new(
-            230..284, 
-            -107..-56, 
-            ivx, 
-            ivy,
-            0, 
-            0
-        );
-        game_state.completes_in_num_steps(max_steps)
-    })
-    .filter(|&v| v)
-    .count();
-    format!("{}", best)
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<SearchError> for SolveError {
+    fn from(e: SearchError) -> Self {
+        SolveError::Search(e)
+    }
+}
+
+/// Read the target area out of the puzzle input.
+fn target_area(input: &str) -> Result<(RangeInclusive<i32>, RangeInclusive<i32>), SolveError> {
+    match parse_target_area(input) {
+        Ok((_, target_area)) => Ok(target_area),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => Err(SolveError::Parse {
+            position: input.len() - e.input.len(),
+        }),
+        Err(nom::Err::Incomplete(_)) => Err(SolveError::Parse {
+            position: input.len(),
+        }),
+    }
+}
+
+/// The highest any hitting shot goes, or an error if the input isn't a target area
+/// or there's no limit to how many shots hit it.
+pub fn solve_part1(input: &str) -> Result<String, SolveError> {
+    let (x_range, y_range) = target_area(input)?;
+    let best = hitting_shots(&x_range, &y_range)?
+        .into_iter()
+        .map(|shot| shot.apex)
+        .max()
+        .unwrap_or_default();
+    Ok(format!("{}", best))
+}
+
+
+/// The number of hitting shots, or an error if the input isn't a target area
+/// or there's no limit to how many shots hit it.
+pub fn solve_part2(input: &str) -> Result<String, SolveError> {
+    let (x_range, y_range) = target_area(input)?;
+    let count = hitting_shots(&x_range, &y_range)?.len();
+    Ok(format!("{}", count))
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use test_case::test_case;
+
     #[test]
     fn real() {
-        assert_eq!(super::solve_part1(), "5671");
-        assert_eq!(super::solve_part2(), "4556");
+        assert_eq!(solve_part1(include_str!("input.txt")).unwrap(), "5671");
+        assert_eq!(solve_part2(include_str!("input.txt")).unwrap(), "4672");
+    }
+
+    #[test]
+    fn example() {
+        let input = "target area: x=20..30, y=-10..-5";
+        assert_eq!(solve_part1(input).unwrap(), "45");
+        assert_eq!(solve_part2(input).unwrap(), "112");
+    }
+
+    #[test]
+    fn infinitely_many_hits() {
+        let input = "target area: x=20..30, y=-5..5";
+        let infinite = Err(SolveError::Search(SearchError::Infinite));
+        assert_eq!(solve_part1(input), infinite);
+        assert_eq!(solve_part2(input), infinite);
+    }
+
+    #[test_case("", 0)]
+    #[test_case("target area: x=20..30", 21)]
+    #[test_case("target area: x=20..a, y=-10..-5", 19)]
+    #[test_case("target area: x=20..30, y=-10..-5\nx=1..2", 33)]
+    fn malformed_input(input: &str, position: usize) {
+        assert_eq!(solve_part1(input), Err(SolveError::Parse { position }));
+        assert_eq!(solve_part2(input), Err(SolveError::Parse { position }));
+    }
+
+    #[test]
+    fn edges_of_target_area_count() {
+        // Landing exactly on the right and top edges of the target area.
+        let mut game_state = GameState::new(230..=284, -107..=-56, 284, -56, 0, 0);
+        assert_eq!(game_state.run(), Outcome::Hit { step: 1 });
+    }
+
+    #[test]
+    fn trajectory_hits() {
+        let mut trajectory = GameState::new(20..=30, -10..=-5, 7, 2, 0, 0).into_iter();
+        let positions = trajectory.by_ref().map(|v| (v.x(), v.y())).collect::<Vec<_>>();
+        assert_eq!(positions, vec![(7, 2), (13, 3), (18, 3), (22, 2), (25, 0), (27, -3), (28, -7)]);
+        assert_eq!(trajectory.outcome(), Some(Outcome::Hit { step: 7 }));
+        assert_eq!(trajectory.steps(), 7);
+    }
+
+    #[test_case(17, -4, Outcome::Overshot)]
+    #[test_case(-3, 5, Outcome::Overshot)]
+    #[test_case(6, 10, Outcome::FellBelow)]
+    #[test_case(5, 3, Outcome::Stalled)]
+    #[test_case(0, 0, Outcome::Stalled)]
+    fn trajectory_misses(vx: i32, vy: i32, expected: Outcome) {
+        let mut trajectory = GameState::new(20..=30, -10..=-5, vx, vy, 0, 0).into_iter();
+        assert!(trajectory.by_ref().count() < 100);
+        assert_eq!(trajectory.outcome(), Some(expected));
+    }
+
+    #[test]
+    fn already_inside() {
+        let mut trajectory = GameState::new(-1..=1, -1..=1, 3, 3, 0, 0).into_iter();
+        assert_eq!(trajectory.next(), None);
+        assert_eq!(trajectory.outcome(), Some(Outcome::Hit { step: 0 }));
     }
 }
\ No newline at end of file
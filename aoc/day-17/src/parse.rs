@@ -0,0 +1,77 @@
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::{digit1, multispace0},
+    combinator::{all_consuming, map_res, opt, recognize},
+    sequence::{pair, preceded, separated_pair, terminated, tuple},
+    IResult,
+};
+
+/// Parse a (possibly negative) integer.
+fn integer<T: FromStr>(s: &str) -> IResult<&str, T> {
+    map_res(recognize(pair(opt(tag("-")), digit1)), str::parse::<T>)(s)
+}
+
+/// Parse an inclusive range like `-107..-56`, regardless of the order of the bounds.
+fn range<T: FromStr + PartialOrd>(s: &str) -> IResult<&str, RangeInclusive<T>> {
+    let (rest, (start, end)) = separated_pair(integer, tag(".."), integer)(s)?;
+    if start <= end {
+        Ok((rest, start..=end))
+    } else {
+        Ok((rest, end..=start))
+    }
+}
+
+/// Parse the puzzle input `target area: x=A..B, y=C..D` into the
+/// (inclusive) ranges along the x and y axes, respectively.
+///
+/// The input must hold nothing else but whitespace around the target area.
+pub fn parse_target_area<T: FromStr + PartialOrd>(
+    s: &str,
+) -> IResult<&str, (RangeInclusive<T>, RangeInclusive<T>)> {
+    let (rest, (_, x_range, _, y_range)) = all_consuming(tuple((
+        preceded(multispace0, tag("target area: x=")),
+        range,
+        tag(", y="),
+        terminated(range, multispace0),
+    )))(s)?;
+    Ok((rest, (x_range, y_range)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzle_input() {
+        let (rest, (x, y)) =
+            parse_target_area::<i32>("target area: x=230..284, y=-107..-56\n").unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(x, 230..=284);
+        assert_eq!(y, -107..=-56);
+    }
+
+    #[test]
+    fn reversed_bounds() {
+        let (_, (x, y)) = parse_target_area::<i64>("target area: x=-20..-30, y=10..5").unwrap();
+        assert_eq!(x, -30..=-20);
+        assert_eq!(y, 5..=10);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse_target_area::<i32>("target area: x=20..30").is_err());
+        assert!(parse_target_area::<i32>("target area: x=20..a, y=-10..-5").is_err());
+        assert!(parse_target_area::<u8>("target area: x=20..300, y=1..5").is_err());
+    }
+
+    #[test]
+    fn trailing_input() {
+        assert!(parse_target_area::<i32>("\n target area: x=20..30, y=-10..-5 \r\n\n").is_ok());
+        assert!(parse_target_area::<i32>("target area: x=20..30, y=-10..-5\nx=1..2").is_err());
+        assert!(parse_target_area::<i32>("target area: x=20..30, y=-10..-56x").is_err());
+        assert!(parse_target_area::<i32>("target area: x=20..30, y=-10..-5, z=1..2").is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::ops::RangeInclusive;
+
+use aoc_core::Vector2;
+
+use crate::{Number, Outcome};
+
+/// The rules that move a probe around, one step at a time.
+///
+/// Every step, the probe first moves by its velocity plus the wind, and then its
+/// velocity changes by the drag plus the gravity. Drag is expected to act against
+/// the velocity without ever reversing it, which is what lets [`Physics::miss`]
+/// give up on shots that can never make it.
+pub trait Physics<T: Number> {
+    /// The change in velocity due to drag, given the current velocity.
+    fn drag(&self, velocity: Vector2<T>) -> Vector2<T>;
+
+    /// The change in velocity due to gravity.
+    fn gravity(&self) -> Vector2<T>;
+
+    /// How far the probe drifts every step, regardless of its velocity.
+    fn wind(&self) -> Vector2<T> {
+        Vector2::zero()
+    }
+
+    /// Where the probe is launched from.
+    fn initial_position(&self) -> Vector2<T> {
+        Vector2::zero()
+    }
+
+    /// Decide whether a probe at `position` moving at `velocity` can never reach
+    /// the target area anymore, and if so, why.
+    ///
+    /// Shots end as soon as this says so, so it must eventually say so for every shot
+    /// that misses. The default does as long as gravity and wind never pull in
+    /// opposite directions along the same axis, since then the probe either stops
+    /// moving along each axis or keeps moving the same way for good.
+    /// Physics where they do pull against each other should override it.
+    fn miss(
+        &self,
+        position: Vector2<T>,
+        velocity: Vector2<T>,
+        target_area: &(RangeInclusive<T>, RangeInclusive<T>),
+    ) -> Option<Outcome> {
+        let (x_range, y_range) = target_area;
+        let (drag, gravity, wind) = (self.drag(velocity), self.gravity(), self.wind());
+
+        let sideways = escape(
+            position.x(),
+            velocity.x(),
+            drag.x() + gravity.x(),
+            gravity.x(),
+            wind.x(),
+            x_range,
+        );
+        match sideways {
+            Some(Escape::Stuck) => return Some(Outcome::Stalled),
+            Some(_) => return Some(Outcome::Overshot),
+            None => {}
+        }
+
+        let vertically = escape(
+            position.y(),
+            velocity.y(),
+            drag.y() + gravity.y(),
+            gravity.y(),
+            wind.y(),
+            y_range,
+        );
+        match vertically {
+            Some(Escape::Stuck) => Some(Outcome::Stalled),
+            Some(Escape::Before) => Some(Outcome::FellBelow),
+            Some(Escape::After) => Some(Outcome::FlewAbove),
+            None => None,
+        }
+    }
+}
+
+/// How a probe can never get inside a range along one axis anymore.
+enum Escape {
+    /// It's before the range and will only ever move away from it.
+    Before,
+    /// It's past the range and will only ever move away from it.
+    After,
+    /// It has stopped moving outside the range.
+    Stuck,
+}
+
+/// Decide whether a probe at `position` along one axis can never get inside `range` anymore,
+/// given its velocity and the change in velocity it's about to go through.
+fn escape<T: Number>(
+    position: T,
+    velocity: T,
+    acceleration: T,
+    gravity: T,
+    wind: T,
+    range: &RangeInclusive<T>,
+) -> Option<Escape> {
+    let zero = T::zero();
+    let (before, after) = (position < *range.start(), position > *range.end());
+    if !before && !after {
+        return None;
+    }
+
+    // Drag only depends on the velocity, so once it balances out gravity, the
+    // velocity never changes again and the probe moves the same distance every step.
+    if acceleration == zero {
+        let step = velocity + wind;
+        return match step {
+            _ if step == zero => Some(Escape::Stuck),
+            _ if before && step < zero => Some(Escape::Before),
+            _ if after && step > zero => Some(Escape::After),
+            _ => None,
+        };
+    }
+
+    // Drag can slow the probe down but never turn it around, so if gravity and wind
+    // don't pull it back either, it never moves back towards the range.
+    if before && gravity <= zero && wind <= zero && velocity + wind <= zero {
+        return Some(Escape::Before);
+    }
+    if after && gravity >= zero && wind >= zero && velocity + wind >= zero {
+        return Some(Escape::After);
+    }
+    None
+}
+
+/// The rules from the puzzle: drag of 1 towards zero sideways, gravity of 1 downwards,
+/// no wind, and launching from the origin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PuzzlePhysics;
+
+impl<T: Number> Physics<T> for PuzzlePhysics {
+    fn drag(&self, velocity: Vector2<T>) -> Vector2<T> {
+        Vector2::new(T::signum(velocity.x()).neg(), T::zero())
+    }
+
+    fn gravity(&self) -> Vector2<T> {
+        Vector2::new(T::zero(), T::one().neg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{velocity_bounds, GameState};
+    use test_case::test_case;
+
+    /// The puzzle rules, but launched from somewhere else with a breeze blowing.
+    #[derive(Clone)]
+    struct Breezy {
+        wind: Vector2<i32>,
+        initial_position: Vector2<i32>,
+    }
+
+    impl Physics<i32> for Breezy {
+        fn drag(&self, velocity: Vector2<i32>) -> Vector2<i32> {
+            PuzzlePhysics.drag(velocity)
+        }
+
+        fn gravity(&self) -> Vector2<i32> {
+            PuzzlePhysics.gravity()
+        }
+
+        fn wind(&self) -> Vector2<i32> {
+            self.wind
+        }
+
+        fn initial_position(&self) -> Vector2<i32> {
+            self.initial_position
+        }
+    }
+
+    /// Simulate every sensible velocity with the given physics,
+    /// returning the best height reached and the number of hits.
+    fn simulate<P: Physics<i32> + Clone>(input: &str, physics: P) -> (i32, usize) {
+        let (_, (x_range, y_range)) = crate::parse_target_area::<i32>(input).unwrap();
+        let (vx_bounds, vy_bounds) = velocity_bounds(&x_range, &y_range);
+
+        let mut best = 0;
+        let mut hits = 0;
+        for vx in vx_bounds {
+            for vy in vy_bounds.clone() {
+                let mut game_state = GameState::with_physics(
+                    x_range.clone(),
+                    y_range.clone(),
+                    vx,
+                    vy,
+                    physics.clone(),
+                );
+                if let Outcome::Hit { .. } = game_state.run() {
+                    best = best.max(game_state.largest_height);
+                    hits += 1;
+                }
+            }
+        }
+        (best, hits)
+    }
+
+    #[test]
+    fn puzzle_physics_reproduces_answers() {
+        assert_eq!(
+            simulate(include_str!("input.txt"), PuzzlePhysics),
+            (5671, 4672)
+        );
+        assert_eq!(
+            simulate("target area: x=20..30, y=-10..-5", PuzzlePhysics),
+            (45, 112)
+        );
+    }
+
+    #[test]
+    fn still_air_from_the_origin_is_the_puzzle() {
+        let still = Breezy {
+            wind: Vector2::new(0, 0),
+            initial_position: Vector2::new(0, 0),
+        };
+        assert_eq!(
+            simulate("target area: x=20..30, y=-10..-5", still),
+            (45, 112)
+        );
+    }
+
+    #[test]
+    fn wind_and_launch_position() {
+        let physics = Breezy {
+            wind: Vector2::new(1, 0),
+            initial_position: Vector2::new(2, 3),
+        };
+        let game_state = GameState::with_physics(20..=30, -10..=-5, 3, 0, physics);
+        assert_eq!(game_state.position, Vector2::new(2, 3));
+
+        let mut trajectory = game_state.into_iter();
+        let positions = trajectory.by_ref().collect::<Vec<_>>();
+        assert_eq!(
+            positions,
+            vec![
+                Vector2::new(6, 3),
+                Vector2::new(9, 2),
+                Vector2::new(11, 0),
+                Vector2::new(12, -3),
+                Vector2::new(13, -7),
+                Vector2::new(14, -12),
+            ]
+        );
+        // Wind keeps pushing the probe sideways, so only the fall can end it.
+        assert_eq!(trajectory.outcome(), Some(Outcome::FellBelow));
+    }
+
+    /// The puzzle rules, but with any gravity and with or without drag.
+    struct Weird {
+        gravity: Vector2<i32>,
+        drag: bool,
+    }
+
+    impl Physics<i32> for Weird {
+        fn drag(&self, velocity: Vector2<i32>) -> Vector2<i32> {
+            match self.drag {
+                true => PuzzlePhysics.drag(velocity),
+                false => Vector2::zero(),
+            }
+        }
+
+        fn gravity(&self) -> Vector2<i32> {
+            self.gravity
+        }
+    }
+
+    #[test_case(Vector2::new(0, 1), true, 7, 2, Outcome::FlewAbove)]
+    #[test_case(Vector2::new(0, 1), true, 7, -20, Outcome::FlewAbove)]
+    #[test_case(Vector2::new(0, 0), true, 7, 0, Outcome::Stalled)]
+    #[test_case(Vector2::new(0, 0), true, 7, 3, Outcome::FlewAbove)]
+    #[test_case(Vector2::new(0, 0), true, 7, -1, Outcome::Hit { step: 5 })]
+    #[test_case(Vector2::new(0, 0), false, 3, 0, Outcome::Stalled)]
+    #[test_case(Vector2::new(0, 0), false, 10, -1, Outcome::Overshot)]
+    #[test_case(Vector2::new(0, 0), false, 4, -1, Outcome::Hit { step: 5 })]
+    #[test_case(Vector2::new(1, -1), false, -5, 0, Outcome::FellBelow)]
+    #[test_case(Vector2::new(0, -1), false, 0, 0, Outcome::Stalled)]
+    fn every_shot_ends(gravity: Vector2<i32>, drag: bool, vx: i32, vy: i32, expected: Outcome) {
+        let physics = Weird { gravity, drag };
+        let mut game_state = GameState::with_physics(20..=30, -10..=-5, vx, vy, physics);
+        assert_eq!(game_state.run(), expected);
+    }
+}
@@ -0,0 +1,448 @@
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+
+/// The steps at which a probe launched with the horizontal velocity `vx` is
+/// within `x_range`, as `(first, last)`.
+///
+/// Since drag eventually stops the probe sideways, `last` is `None` if it stalls
+/// inside the range and so stays there forever. Returns `None` if the probe never
+/// makes it into the range at all.
+pub fn x_window(vx: i32, x_range: &RangeInclusive<i32>) -> Option<(usize, Option<usize>)> {
+    // Overshooting the range by a whole step may not fit in an `i32`.
+    let x_range = i64::from(*x_range.start())..=i64::from(*x_range.end());
+    let (mut x, mut v) = (0, i64::from(vx));
+    let mut window: Option<(usize, Option<usize>)> = None;
+
+    for step in 1.. {
+        x += v;
+        v -= v.signum();
+
+        if x_range.contains(&x) {
+            let first = window.map_or(step, |(first, _)| first);
+            window = Some((first, Some(step)));
+            if v == 0 {
+                return Some((first, None));
+            }
+        }
+
+        let past_the_range = (vx > 0 && x > *x_range.end()) || (vx < 0 && x < *x_range.start());
+        if v == 0 || past_the_range {
+            break;
+        }
+    }
+    window
+}
+
+/// The steps at which a probe launched with the vertical velocity `vy` is within `y_range`.
+///
+/// These need not be contiguous: a target above the starting point can
+/// be passed through once on the way up and once more on the way down.
+pub fn y_steps(vy: i32, y_range: &RangeInclusive<i32>) -> Vec<usize> {
+    let y_range = i64::from(*y_range.start())..=i64::from(*y_range.end());
+    let (mut y, mut v) = (0, i64::from(vy));
+    let mut steps = vec![];
+
+    for step in 1.. {
+        y += v;
+        v -= 1;
+
+        if y_range.contains(&y) {
+            steps.push(step);
+        }
+        // Falling and already below the target, so it'll never come back up.
+        if v < 0 && y < *y_range.start() {
+            break;
+        }
+    }
+    steps
+}
+
+/// The smallest `n` such that the `n`-th triangular number is at least `distance`,
+/// i.e. the slowest launch that drag doesn't stop before covering the distance.
+fn slowest_to_cover(distance: i64) -> i32 {
+    let mut n = ((2.0 * distance as f64).sqrt() as i64).max(0);
+    while n * (n + 1) / 2 < distance {
+        n += 1;
+    }
+    while n > 0 && (n - 1) * n / 2 >= distance {
+        n -= 1;
+    }
+    // Even the widest distance between two `i32`s only needs a launch of about 2^16.
+    n as i32
+}
+
+/// The initial velocities along the x and y axes outside of which
+/// no probe can ever hit the target area.
+pub fn velocity_bounds(
+    x_range: &RangeInclusive<i32>,
+    y_range: &RangeInclusive<i32>,
+) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+    let (x_min, x_max) = (*x_range.start(), *x_range.end());
+    let (y_min, y_max) = (*y_range.start(), *y_range.end());
+
+    // Too fast and we skip past the target on the first step. Too slow and drag
+    // stops us before we get there, where the farthest we can go with
+    // velocity `n` is the `n`-th triangular number.
+    let vx = if x_min > 0 {
+        slowest_to_cover(x_min.into())..=x_max
+    } else if x_max < 0 {
+        x_min..=-slowest_to_cover(-i64::from(x_max))
+    } else {
+        x_min..=x_max
+    };
+
+    // Launched upwards at `vy`, we come back down through the starting height at `-vy`,
+    // so the step after that lands at `-(vy + 1)`, which must not be below the target.
+    // Launched downwards, the first step must not already be below the target.
+    let vy = y_min.min(0)..=y_max.max(-1 - y_min);
+
+    (vx, vy)
+}
+
+/// A shot that hits the target area.
+///
+/// Shots order by their initial velocity first, so sorting a bunch of them
+/// gives the same order as [`hitting_velocities`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Shot {
+    /// The initial velocity, as `(vx, vy)`.
+    pub velocity: (i32, i32),
+    /// The (1-based) step at which the probe first enters the target area.
+    pub step: usize,
+    /// The highest point of the whole trajectory.
+    pub apex: i32,
+    /// Where the probe is when it first enters the target area.
+    pub entry: (i32, i32),
+}
+
+impl Shot {
+    /// The header line matching [`Shot::to_csv_row`].
+    pub const CSV_HEADER: &'static str = "vx,vy,step,apex,entry_x,entry_y";
+
+    /// Format the shot as a line of CSV, without the newline.
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.velocity.0, self.velocity.1, self.step, self.apex, self.entry.0, self.entry.1
+        )
+    }
+}
+
+/// Write the shots as CSV, header included.
+pub fn write_csv<W: Write>(shots: &[Shot], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{}", Shot::CSV_HEADER)?;
+    for shot in shots {
+        writeln!(writer, "{}", shot.to_csv_row())?;
+    }
+    Ok(())
+}
+
+/// Where a probe launched at `(vx, vy)` is after `step` steps,
+/// or `None` if that's too far away to fit in an `i32`.
+pub fn position_at(vx: i32, vy: i32, step: usize) -> Option<(i32, i32)> {
+    let (vx, vy) = (i64::from(vx), i64::from(vy));
+    // Sideways, drag stops the probe after `|vx|` steps.
+    let t = i64::try_from(step).ok()?.min(vx.abs());
+    let x = vx * t - vx.signum() * t * (t - 1) / 2;
+    let t = i64::try_from(step).ok()?;
+    let y = vy.checked_mul(t)?.checked_sub(t.checked_mul(t - 1)? / 2)?;
+    Some((x.try_into().ok()?, y.try_into().ok()?))
+}
+
+/// Why the shots hitting a target area can't be listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchError {
+    /// Infinitely many shots hit the target area, which happens when the probe can
+    /// stall sideways inside a target area that straddles the starting height.
+    Infinite,
+    /// The highest shots that might hit the target area fly higher than an `i32` can hold,
+    /// which happens for targets reaching below `y = -65536` or above `y = 65535`.
+    TooHigh,
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::Infinite => write!(f, "infinitely many velocities hit this target area"),
+            SearchError::TooHigh => write!(
+                f,
+                "shots at this target area may fly higher than {}",
+                i32::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// Every shot that lands inside the target area, in increasing order of initial velocity,
+/// or [`SearchError::Infinite`] if there are infinitely many.
+///
+/// Any target area between `i32`s can be searched, as long as the fastest upward launch
+/// that might hit it doesn't fly higher than `i32::MAX`. Otherwise the search fails
+/// with [`SearchError::TooHigh`].
+pub fn hitting_shots(
+    x_range: &RangeInclusive<i32>,
+    y_range: &RangeInclusive<i32>,
+) -> Result<Vec<Shot>, SearchError> {
+    let (vx_bounds, vy_bounds) = velocity_bounds(x_range, y_range);
+    // Every slower launch peaks lower, so only the fastest one needs checking.
+    apex(*vy_bounds.end()).ok_or(SearchError::TooHigh)?;
+
+    let x_windows = vx_bounds
+        .filter_map(|vx| x_window(vx, x_range).map(|window| (vx, window)))
+        .collect::<Vec<_>>();
+
+    // If the target straddles the starting height, then however high we launch we'll
+    // come back down through it at step `2 * vy + 1`, which only helps while we're
+    // still inside it sideways. If we stall in there, every upward launch is a hit.
+    let mut vy_max = *vy_bounds.end();
+    if y_range.contains(&0) {
+        for (_, (_, last)) in &x_windows {
+            let last = last.ok_or(SearchError::Infinite)?;
+            vy_max = vy_max.max((last as i32 - 1) / 2);
+        }
+    }
+
+    let mut shots = vec![];
+    for vy in *vy_bounds.start()..=vy_max {
+        let steps = y_steps(vy, y_range);
+        if steps.is_empty() {
+            continue;
+        }
+        for &(vx, (first, last)) in &x_windows {
+            let entered = steps
+                .iter()
+                .find(|&&step| step >= first && last.is_none_or(|last| step <= last));
+            if let Some(&step) = entered {
+                shots.push(Shot {
+                    velocity: (vx, vy),
+                    step,
+                    apex: apex(vy).ok_or(SearchError::TooHigh)?,
+                    entry: position_at(vx, vy, step).ok_or(SearchError::TooHigh)?,
+                });
+            }
+        }
+    }
+    shots.sort_unstable();
+    Ok(shots)
+}
+
+/// Every initial velocity, as `(vx, vy)`, with which the probe eventually
+/// lands inside the target area, in increasing order.
+///
+/// Like [`hitting_shots`], fails if there are infinitely many.
+pub fn hitting_velocities(
+    x_range: &RangeInclusive<i32>,
+    y_range: &RangeInclusive<i32>,
+) -> Result<Vec<(i32, i32)>, SearchError> {
+    let shots = hitting_shots(x_range, y_range)?;
+    Ok(shots.into_iter().map(|shot| shot.velocity).collect())
+}
+
+/// The highest point reached by a probe launched upwards at `vy`,
+/// or `None` if it's higher than an `i32` can hold.
+pub fn apex(vy: i32) -> Option<i32> {
+    if vy > 0 {
+        let vy = i64::from(vy);
+        (vy * (vy + 1) / 2).try_into().ok()
+    } else {
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState, Outcome};
+    use aoc_core::Vector2;
+    use test_case::test_case;
+
+    /// Simulate every velocity in a generous box, the slow way.
+    fn brute_force(
+        x_range: &RangeInclusive<i32>,
+        y_range: &RangeInclusive<i32>,
+    ) -> Vec<(i32, i32)> {
+        let bound = 2 * [
+            x_range.start(),
+            x_range.end(),
+            y_range.start(),
+            y_range.end(),
+        ]
+        .iter()
+        .map(|v| v.abs())
+        .max()
+        .unwrap();
+        let mut velocities = vec![];
+        for vx in -bound..=bound {
+            for vy in -bound..=bound {
+                let mut game_state = GameState::new(x_range.clone(), y_range.clone(), vx, vy, 0, 0);
+                if let Outcome::Hit { .. } = game_state.run() {
+                    velocities.push((vx, vy));
+                }
+            }
+        }
+        velocities
+    }
+
+    #[test_case(20..=30, -10..=-5)]
+    #[test_case(-30..=-20, -10..=-5)]
+    #[test_case(20..=30, 5..=10)]
+    #[test_case(-30..=-20, 5..=10)]
+    #[test_case(-5..=5, -10..=-5)]
+    #[test_case(25..=27, -3..=4)]
+    #[test_case(-27..=-25, 0..=0)]
+    #[test_case(3..=3, 2..=2)]
+    #[test_case(0..=0, -6..=-6)]
+    fn agrees_with_simulation(x_range: RangeInclusive<i32>, y_range: RangeInclusive<i32>) {
+        assert_eq!(
+            hitting_velocities(&x_range, &y_range),
+            Ok(brute_force(&x_range, &y_range))
+        );
+    }
+
+    #[test_case(20..=30, -10..=-5)]
+    #[test_case(20..=30, 5..=10)]
+    #[test_case(-30..=-20, -10..=-5)]
+    #[test_case(25..=27, -3..=4)]
+    fn shots_agree_with_simulation(x_range: RangeInclusive<i32>, y_range: RangeInclusive<i32>) {
+        for shot in hitting_shots(&x_range, &y_range).unwrap() {
+            let (vx, vy) = shot.velocity;
+            let game_state = GameState::new(x_range.clone(), y_range.clone(), vx, vy, 0, 0);
+            let positions = game_state.into_iter().collect::<Vec<_>>();
+            assert_eq!(positions.len(), shot.step, "{:?}", shot);
+            let entry = positions[shot.step - 1];
+            assert_eq!(
+                entry,
+                Vector2::new(shot.entry.0, shot.entry.1),
+                "{:?}",
+                shot
+            );
+        }
+    }
+
+    #[test]
+    fn shot_details() {
+        let shots = hitting_shots(&(20..=30), &(-10..=-5)).unwrap();
+        let best = shots.iter().map(|shot| shot.apex).max();
+        assert_eq!(best, Some(45));
+        let shot = shots.iter().find(|shot| shot.velocity == (6, 9)).unwrap();
+        assert_eq!(
+            *shot,
+            Shot {
+                velocity: (6, 9),
+                step: 20,
+                apex: 45,
+                entry: (21, -10),
+            }
+        );
+        assert_eq!(shots.len(), 112);
+        assert_eq!(
+            shots.iter().map(|shot| shot.velocity).collect::<Vec<_>>(),
+            hitting_velocities(&(20..=30), &(-10..=-5)).unwrap()
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let shots = hitting_shots(&(20..=30), &(-10..=-5)).unwrap();
+        let mut out = vec![];
+        write_csv(&shots[..2], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "vx,vy,step,apex,entry_x,entry_y\n6,0,5,0,20,-10\n6,1,5,1,20,-5\n"
+        );
+    }
+
+    #[test_case(7, 2, 1, (7, 2))]
+    #[test_case(7, 2, 7, (28, -7))]
+    #[test_case(7, 2, 10, (28, -25))]
+    #[test_case(-3, -1, 5, (-6, -15))]
+    #[test_case(65_535, 65_535, 131_072, (2_147_450_880, -65_536))]
+    #[test_case(-65_535, 32_767, 65_536, (-2_147_450_880, -32_768))]
+    fn positions(vx: i32, vy: i32, step: usize, expected: (i32, i32)) {
+        assert_eq!(position_at(vx, vy, step), Some(expected));
+    }
+
+    #[test_case(65_536, 0, 65_536)]
+    #[test_case(-65_536, 0, 65_536 ; "far to the left")]
+    #[test_case(0, 65_536, 65_537)]
+    #[test_case(0, 0, usize::MAX)]
+    fn positions_too_far(vx: i32, vy: i32, step: usize) {
+        assert_eq!(position_at(vx, vy, step), None);
+    }
+
+    #[test_case(-5, Some(0))]
+    #[test_case(9, Some(45))]
+    #[test_case(65_535, Some(2_147_450_880))]
+    #[test_case(65_536, None)]
+    #[test_case(i32::MAX, None)]
+    fn apexes(vy: i32, expected: Option<i32>) {
+        assert_eq!(apex(vy), expected);
+    }
+
+    #[test_case(20..=30, -70_010..=-70_000)]
+    #[test_case(20..=30, i32::MIN..=i32::MIN)]
+    #[test_case(20..=30, 65_536..=70_000)]
+    #[test_case(i32::MAX..=i32::MAX, i32::MIN..=i32::MAX)]
+    fn too_deep_or_high(x_range: RangeInclusive<i32>, y_range: RangeInclusive<i32>) {
+        assert_eq!(hitting_shots(&x_range, &y_range), Err(SearchError::TooHigh));
+    }
+
+    #[test]
+    fn deepest_bounds() {
+        assert_eq!(
+            velocity_bounds(&(20..=30), &(-65_536..=-65_530)),
+            (6..=30, -65_536..=65_535)
+        );
+        assert_eq!(apex(65_535), Some(2_147_450_880));
+        assert_eq!(
+            velocity_bounds(&(i32::MIN..=i32::MIN), &(i32::MIN..=i32::MIN)),
+            (i32::MIN..=-65_536, i32::MIN..=i32::MAX)
+        );
+    }
+
+    #[test_case(-5..=5, -3..=4)]
+    #[test_case(20..=30, -5..=5)]
+    #[test_case(-30..=-20, 0..=0)]
+    fn infinitely_many(x_range: RangeInclusive<i32>, y_range: RangeInclusive<i32>) {
+        assert_eq!(
+            hitting_velocities(&x_range, &y_range),
+            Err(SearchError::Infinite)
+        );
+        assert_eq!(
+            hitting_shots(&x_range, &y_range),
+            Err(SearchError::Infinite)
+        );
+        assert_eq!(
+            SearchError::Infinite.to_string(),
+            "infinitely many velocities hit this target area"
+        );
+    }
+
+    #[test_case(1, 1)]
+    #[test_case(6, 3)]
+    #[test_case(7, 4)]
+    #[test_case(20, 6)]
+    #[test_case(21, 6)]
+    #[test_case(22, 7)]
+    #[test_case(230, 21)]
+    #[test_case(2_147_450_880, 65_535)]
+    #[test_case(2_147_450_881, 65_536)]
+    #[test_case(2_147_483_648, 65_536)]
+    fn slowest(distance: i64, expected: i32) {
+        assert_eq!(slowest_to_cover(distance), expected);
+    }
+
+    #[test]
+    fn windows() {
+        assert_eq!(x_window(6, &(20..=30)), Some((5, None)));
+        assert_eq!(x_window(7, &(20..=30)), Some((4, None)));
+        assert_eq!(x_window(10, &(20..=30)), Some((3, Some(3))));
+        assert_eq!(x_window(30, &(20..=30)), Some((1, Some(1))));
+        assert_eq!(x_window(5, &(20..=30)), None);
+        assert_eq!(y_steps(2, &(-10..=-5)), vec![7]);
+        assert_eq!(y_steps(3, &(2..=3)), vec![1, 6]);
+    }
+}
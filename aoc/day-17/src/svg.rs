@@ -0,0 +1,232 @@
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use num_traits::ToPrimitive;
+
+use crate::{GameState, Number, Outcome, Physics};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 600.0;
+const MARGIN: f64 = 40.0;
+
+/// A shot, as it's drawn.
+#[derive(Clone, Debug, PartialEq)]
+struct Shot {
+    velocity: (f64, f64),
+    /// Every position the probe goes through, starting with the launch position.
+    points: Vec<(f64, f64)>,
+    outcome: Outcome,
+}
+
+impl Shot {
+    /// The highest point along the shot, or the first one if there's a tie.
+    fn apex(&self) -> (f64, f64) {
+        self.points
+            .iter()
+            .copied()
+            .fold(
+                self.points[0],
+                |apex, point| if point.1 > apex.1 { point } else { apex },
+            )
+    }
+}
+
+/// An SVG plot of the target area and a few trajectories through it.
+///
+/// Hits are drawn in green and misses in red, and every trajectory's apex is marked.
+/// The axes are scaled independently to fit the canvas, since the shots reaching the
+/// highest tend to be a lot taller than they are wide.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plot {
+    target_area: (RangeInclusive<f64>, RangeInclusive<f64>),
+    shots: Vec<Shot>,
+}
+
+fn to_f64<T: ToPrimitive>(value: T) -> f64 {
+    value.to_f64().expect("Coordinates should fit in an f64.")
+}
+
+impl Plot {
+    pub fn new<T: Number + ToPrimitive>(
+        x_range: RangeInclusive<T>,
+        y_range: RangeInclusive<T>,
+    ) -> Self {
+        let (x_min, x_max) = x_range.into_inner();
+        let (y_min, y_max) = y_range.into_inner();
+        Self {
+            target_area: (to_f64(x_min)..=to_f64(x_max), to_f64(y_min)..=to_f64(y_max)),
+            shots: vec![],
+        }
+    }
+
+    /// Simulate the shot and add its trajectory to the plot.
+    pub fn add<T, P>(&mut self, game_state: GameState<T, P>) -> &mut Self
+    where
+        T: Number + ToPrimitive,
+        P: Physics<T>,
+    {
+        let velocity = game_state.initial_velocities;
+        let start = game_state.position;
+        let mut trajectory = game_state.into_iter();
+
+        let mut points = vec![(to_f64(start.x()), to_f64(start.y()))];
+        points.extend(
+            trajectory
+                .by_ref()
+                .map(|position| (to_f64(position.x()), to_f64(position.y()))),
+        );
+
+        self.shots.push(Shot {
+            velocity: (to_f64(velocity.x()), to_f64(velocity.y())),
+            points,
+            outcome: trajectory
+                .outcome()
+                .expect("A finished trajectory should know how it ended."),
+        });
+        self
+    }
+
+    /// The smallest box containing the target area and every trajectory, as `(x_min, x_max, y_min, y_max)`.
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let (x_range, y_range) = &self.target_area;
+        let mut bounds = (
+            *x_range.start(),
+            *x_range.end(),
+            *y_range.start(),
+            *y_range.end(),
+        );
+        for &(x, y) in self.shots.iter().flat_map(|shot| &shot.points) {
+            bounds = (
+                bounds.0.min(x),
+                bounds.1.max(x),
+                bounds.2.min(y),
+                bounds.3.max(y),
+            );
+        }
+        bounds
+    }
+
+    /// Render the plot as a standalone SVG document.
+    pub fn to_svg(&self) -> String {
+        let (x_min, x_max, y_min, y_max) = self.bounds();
+        // Keep degenerate plots (a single point, say) from dividing by zero.
+        let x_scale = (WIDTH - 2.0 * MARGIN) / (x_max - x_min).max(1.0);
+        let y_scale = (HEIGHT - 2.0 * MARGIN) / (y_max - y_min).max(1.0);
+        // SVG's y axis points down, so flip it.
+        let project = |(x, y): (f64, f64)| {
+            (
+                MARGIN + (x - x_min) * x_scale,
+                HEIGHT - MARGIN - (y - y_min) * y_scale,
+            )
+        };
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">"#
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+        let (x_range, y_range) = &self.target_area;
+        let (left, top) = project((*x_range.start(), *y_range.end()));
+        let (right, bottom) = project((*x_range.end(), *y_range.start()));
+        writeln!(
+            svg,
+            r#"<rect class="target" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="steelblue" fill-opacity="0.3" stroke="steelblue"/>"#,
+            left,
+            top,
+            right - left,
+            bottom - top,
+        )
+        .unwrap();
+
+        for shot in &self.shots {
+            let (color, label) = match shot.outcome {
+                Outcome::Hit { step } => ("green", format!("hit after {} steps", step)),
+                Outcome::Overshot => ("red", "overshot".to_owned()),
+                Outcome::FellBelow => ("red", "fell below".to_owned()),
+                Outcome::FlewAbove => ("red", "flew above".to_owned()),
+                Outcome::Stalled => ("red", "stalled".to_owned()),
+            };
+            let points = shot
+                .points
+                .iter()
+                .map(|&point| {
+                    let (x, y) = project(point);
+                    format!("{:.2},{:.2}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                svg,
+                r#"<polyline class="trajectory" points="{}" fill="none" stroke="{}"><title>velocity ({}, {}): {}</title></polyline>"#,
+                points, color, shot.velocity.0, shot.velocity.1, label,
+            )
+            .unwrap();
+
+            let apex = shot.apex();
+            let (x, y) = project(apex);
+            writeln!(
+                svg,
+                r#"<circle class="apex" cx="{:.2}" cy="{:.2}" r="3" fill="{}"><title>apex ({}, {})</title></circle>"#,
+                x, y, color, apex.0, apex.1,
+            )
+            .unwrap();
+        }
+
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_target_trajectories_and_apexes() {
+        let mut plot = Plot::new(20..=30, -10..=-5);
+        plot.add(GameState::new(20..=30, -10..=-5, 6, 9, 0, 0))
+            .add(GameState::new(20..=30, -10..=-5, 17, -4, 0, 0));
+        let svg = plot.to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches(r#"class="target""#).count(), 1);
+        assert_eq!(svg.matches(r#"class="trajectory""#).count(), 2);
+        assert_eq!(svg.matches(r#"class="apex""#).count(), 2);
+        assert!(svg.contains("velocity (6, 9): hit after 20 steps"));
+        assert!(svg.contains("velocity (17, -4): overshot"));
+        assert!(svg.contains("apex (21, 45)"));
+        assert!(svg.contains("apex (0, 0)"));
+    }
+
+    #[test]
+    fn fits_the_canvas() {
+        let mut plot = Plot::new(20..=30, -10..=-5);
+        plot.add(GameState::new(20..=30, -10..=-5, 6, 9, 0, 0));
+        let shot = &plot.shots[0];
+        assert_eq!(shot.points.len(), 21);
+        assert_eq!(shot.apex(), (21.0, 45.0));
+        assert_eq!(plot.bounds(), (0.0, 30.0, -10.0, 45.0));
+
+        let svg = plot.to_svg();
+        // The launch position sits in the bottom left, above the target area's bottom.
+        let start = format!(
+            "{:.2},{:.2} ",
+            MARGIN,
+            HEIGHT - MARGIN - 10.0 * (HEIGHT - 2.0 * MARGIN) / 55.0
+        );
+        assert!(svg.contains(&format!(r#"points="{}"#, start)));
+    }
+
+    #[test]
+    fn single_point() {
+        let mut plot = Plot::new(0..=0, 0..=0);
+        plot.add(GameState::new(0..=0, 0..=0, 0, 0, 0, 0));
+        let svg = plot.to_svg();
+        assert!(!svg.contains("NaN"));
+        assert!(svg.contains("hit after 0 steps"));
+    }
+}
@@ -0,0 +1,66 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use day_18::{
+    build_numbers, max_pair_magnitude, max_pair_magnitude_pruned, solve_part1_with,
+    solve_part2_with, FlatTree, ReductionRules, SnailfishNumber, Tree,
+};
+
+const INPUT: &str = include_str!("../src/input.txt");
+
+fn add<N: SnailfishNumber>(numbers: &[N]) -> usize {
+    let mut sum = numbers[0].clone();
+    sum.add(&numbers[1]);
+    sum.magnitude()
+}
+
+fn bench_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add");
+    let trees = build_numbers::<Tree>(INPUT).unwrap();
+    let flat = build_numbers::<FlatTree>(INPUT).unwrap();
+    group.bench_function(BenchmarkId::new("Tree", "first two"), |b| {
+        b.iter(|| add(black_box(&trees)))
+    });
+    group.bench_function(BenchmarkId::new("FlatTree", "first two"), |b| {
+        b.iter(|| add(black_box(&flat)))
+    });
+    group.finish();
+}
+
+fn bench_part1(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part1");
+    group.bench_function("Tree", |b| {
+        b.iter(|| solve_part1_with::<Tree>(black_box(INPUT)))
+    });
+    group.bench_function("FlatTree", |b| {
+        b.iter(|| solve_part1_with::<FlatTree>(black_box(INPUT)))
+    });
+    group.finish();
+}
+
+fn bench_part2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part2");
+    group.sample_size(10);
+    group.bench_function("Tree", |b| {
+        b.iter(|| solve_part2_with::<Tree>(black_box(INPUT)))
+    });
+    group.bench_function("FlatTree", |b| {
+        b.iter(|| solve_part2_with::<FlatTree>(black_box(INPUT)))
+    });
+    group.finish();
+}
+
+fn bench_pairs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pairs");
+    group.sample_size(10);
+    let flat = build_numbers::<FlatTree>(INPUT).unwrap();
+    let rules = ReductionRules::PUZZLE;
+    group.bench_function("all", |b| {
+        b.iter(|| max_pair_magnitude(black_box(&flat), &rules))
+    });
+    group.bench_function("pruned", |b| {
+        b.iter(|| max_pair_magnitude_pruned(black_box(&flat), &rules))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_add, bench_part1, bench_part2, bench_pairs);
+criterion_main!(benches);
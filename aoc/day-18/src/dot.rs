@@ -0,0 +1,137 @@
+use std::fmt::Write;
+
+use crate::{tree::depth_of, ReductionRules, Tree};
+
+/// The fill for pairs that are deep enough to explode.
+const EXPLODE_FILL: &str = "#f4a582";
+/// The fill for regular numbers that are large enough to split.
+const SPLIT_FILL: &str = "#92c5de";
+
+impl Tree {
+    /// Draw the tree as a Graphviz DOT graph, highlighting what the puzzle rules
+    /// would explode or split.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Draw the tree as a Graphviz DOT graph, e.g. for `dot -Tsvg`.
+    ///
+    /// Pairs are drawn as small circles and regular numbers as boxes, with every
+    /// pair nested inside at least `rules.explode_depth()` pairs filled in orange and
+    /// every regular number that's at least `rules.split_threshold()` filled in blue.
+    /// Nodes are named after their index in the tree, so that the drawings
+    /// from before and after a reduction step line up.
+    pub fn to_dot_with(&self, rules: &ReductionRules) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph snailfish {{").unwrap();
+        writeln!(dot, "    ordering=out;").unwrap();
+        writeln!(dot, "    node [fontname=\"monospace\"];").unwrap();
+        if !self.is_empty() {
+            self.write_dot_node(&mut dot, 0, rules);
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    /// Write the node at `index` and everything below it.
+    fn write_dot_node(&self, dot: &mut String, index: usize, rules: &ReductionRules) {
+        match self.at(index) {
+            (_, Some(value)) if value >= rules.split_threshold() => writeln!(
+                dot,
+                "    n{} [shape=box, label=\"{}\", class=\"split\", style=filled, fillcolor=\"{}\"];",
+                index, value, SPLIT_FILL
+            )
+            .unwrap(),
+            (_, Some(value)) => {
+                writeln!(dot, "    n{} [shape=box, label=\"{}\"];", index, value).unwrap()
+            }
+            (_, None) => {
+                write!(dot, "    n{} [shape=circle, label=\"\", width=0.2", index).unwrap();
+                if depth_of(index) >= rules.explode_depth() {
+                    write!(
+                        dot,
+                        ", class=\"explode\", style=filled, fillcolor=\"{}\"",
+                        EXPLODE_FILL
+                    )
+                    .unwrap();
+                }
+                writeln!(dot, "];").unwrap();
+                for child in [2 * index + 1, 2 * index + 2] {
+                    writeln!(dot, "    n{} -> n{};", index, child).unwrap();
+                    self.write_dot_node(dot, child, rules);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_pairs_and_regular_numbers() {
+        let dot = Tree::from("[[1,2],3]").to_dot();
+        assert!(dot.starts_with("digraph snailfish {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches("shape=circle").count(), 2);
+        assert_eq!(dot.matches("shape=box").count(), 3);
+        assert!(dot.contains("    n3 [shape=box, label=\"1\"];\n"));
+        assert!(dot.contains("    n2 [shape=box, label=\"3\"];\n"));
+        // Left children come before right ones, which `ordering=out` keeps.
+        let edges = dot
+            .lines()
+            .filter(|line| line.contains("->"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            [
+                "    n0 -> n1;",
+                "    n1 -> n3;",
+                "    n1 -> n4;",
+                "    n0 -> n2;"
+            ]
+        );
+        assert!(!dot.contains("explode"));
+        assert!(!dot.contains("split"));
+    }
+
+    #[test]
+    fn highlights_explosions_and_splits() {
+        let dot = Tree::from("[[[[[9,8],1],2],3],4]").to_dot();
+        assert_eq!(dot.matches("class=\"explode\"").count(), 1);
+        assert!(dot.contains("    n15 [shape=circle, label=\"\", width=0.2, class=\"explode\""));
+
+        let mut tree = Tree::from("[[[[0,7],4],[15,[0,13]]],[1,1]]");
+        assert_eq!(tree.to_dot().matches("class=\"split\"").count(), 2);
+        tree.reduce();
+        let dot = tree.to_dot();
+        assert_eq!(dot.matches("class=\"split\"").count(), 1);
+        assert!(dot.contains("    n9 [shape=circle, label=\"\", width=0.2];\n"));
+        // The 13 splits into a pair that's deep enough to explode.
+        tree.reduce();
+        let dot = tree.to_dot();
+        assert!(!dot.contains("class=\"split\""));
+        assert_eq!(dot.matches("class=\"explode\"").count(), 1);
+        assert!(dot.contains("    n22 [shape=circle, label=\"\", width=0.2, class=\"explode\""));
+    }
+
+    #[test]
+    fn follows_the_rules() {
+        let tree = Tree::from("[[1,7],3]");
+        let rules = ReductionRules::new(1, 5).unwrap();
+        let dot = tree.to_dot_with(&rules);
+        assert_eq!(dot.matches("class=\"explode\"").count(), 1);
+        assert!(dot.contains("    n4 [shape=box, label=\"7\", class=\"split\""));
+    }
+
+    #[test]
+    fn regular_number_and_empty_tree() {
+        let dot = Tree::from("12").to_dot();
+        assert!(dot.contains("    n0 [shape=box, label=\"12\", class=\"split\""));
+        assert_eq!(
+            Tree::new().to_dot(),
+            "digraph snailfish {\n    ordering=out;\n    node [fontname=\"monospace\"];\n}\n"
+        );
+    }
+}
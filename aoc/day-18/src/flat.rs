@@ -0,0 +1,248 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{ParseError, ReductionRules, SnailFish, SnailfishNumber};
+
+/// A regular number along with the number of pairs it's nested inside.
+pub type Leaf = (usize, usize);
+
+/// A snailfish number stored as its regular numbers, from left to right,
+/// each tagged with its depth.
+///
+/// That's enough to recover the shape of the number, since the leaves of a binary tree
+/// read in order along with their depths describe it uniquely. Unlike [`Tree`](crate::Tree),
+/// which needs `2^depth` slots, this only ever stores one entry per regular number,
+/// and adding two numbers is just appending one to the other.
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
+pub struct FlatTree {
+    leaves: Vec<Leaf>,
+}
+
+impl Clone for FlatTree {
+    fn clone(&self) -> Self {
+        Self {
+            leaves: self.leaves.clone(),
+        }
+    }
+
+    /// Reuse our buffer rather than allocating a new one.
+    fn clone_from(&mut self, source: &Self) {
+        self.leaves.clone_from(&source.leaves);
+    }
+}
+
+impl FlatTree {
+    /// The regular numbers, from left to right, as `(depth, value)`.
+    pub fn leaves(&self) -> &[Leaf] {
+        &self.leaves
+    }
+
+    /// Explode the left-most pair of regular numbers nested inside four pairs, if any.
+    /// Returns true if a pair exploded, false otherwise.
+    pub fn explode(&mut self) -> bool {
+        self.explode_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Explode the left-most pair of regular numbers nested inside
+    /// more than `rules.explode_depth()` pairs, if any.
+    pub fn explode_with(&mut self, rules: &ReductionRules) -> bool {
+        // The first two neighbours at the same depth are always a pair: if the left one
+        // was a right child instead, its left sibling would hold an earlier such pair.
+        let index = self
+            .leaves
+            .windows(2)
+            .position(|w| w[0].0 > rules.explode_depth() && w[0].0 == w[1].0);
+        let Some(index) = index else {
+            return false;
+        };
+
+        let (depth, left) = self.leaves[index];
+        let (_, right) = self.leaves.remove(index + 1);
+        if index > 0 {
+            self.leaves[index - 1].1 += left;
+        }
+        if let Some((_, value)) = self.leaves.get_mut(index + 1) {
+            *value += right;
+        }
+        self.leaves[index] = (depth - 1, 0);
+        true
+    }
+
+    /// Split the left-most regular number that's at least 10 into a pair of its halves,
+    /// rounded down and up, respectively. Returns true if a number was split, false otherwise.
+    pub fn split(&mut self) -> bool {
+        self.split_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Split the left-most regular number that's at least `rules.split_threshold()`, if any.
+    pub fn split_with(&mut self, rules: &ReductionRules) -> bool {
+        let index = self
+            .leaves
+            .iter()
+            .position(|&(_, value)| value >= rules.split_threshold());
+        let Some(index) = index else {
+            return false;
+        };
+
+        let (depth, value) = self.leaves[index];
+        let (left, right) = rules.halves(value);
+        self.leaves[index] = (depth + 1, left);
+        self.leaves.insert(index + 1, (depth + 1, right));
+        true
+    }
+
+    /// Write the pair (or regular number) at `depth` whose left-most leaf is `leaves[*next]`.
+    fn fmt_node(
+        &self,
+        depth: usize,
+        next: &mut usize,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        let (leaf_depth, value) = self.leaves[*next];
+        if leaf_depth == depth {
+            *next += 1;
+            return write!(f, "{}", value);
+        }
+        write!(f, "[")?;
+        self.fmt_node(depth + 1, next, f)?;
+        write!(f, ",")?;
+        self.fmt_node(depth + 1, next, f)?;
+        write!(f, "]")
+    }
+}
+
+impl SnailfishNumber for FlatTree {
+    fn add_with(&mut self, rhs: &Self, rules: &ReductionRules) {
+        self.leaves.extend_from_slice(&rhs.leaves);
+        for (depth, _) in self.leaves.iter_mut() {
+            *depth += 1;
+        }
+        self.reduce_all_the_way_with(rules);
+    }
+
+    fn reduce_with(&mut self, rules: &ReductionRules) -> bool {
+        self.explode_with(rules) || self.split_with(rules)
+    }
+
+    /// Fold the leaves back into pairs from left to right, the way a shift-reduce parser would.
+    fn magnitude_with(&self, rules: &ReductionRules) -> usize {
+        let mut stack: Vec<Leaf> = Vec::with_capacity(self.leaves.len());
+        for &leaf in &self.leaves {
+            stack.push(leaf);
+            while let [.., (left_depth, left), (right_depth, right)] = stack[..] {
+                if left_depth != right_depth {
+                    break;
+                }
+                stack.truncate(stack.len() - 2);
+                stack.push((left_depth - 1, rules.magnitude(left, right)));
+            }
+        }
+        stack.first().map_or(0, |&(_, magnitude)| magnitude)
+    }
+}
+
+impl From<SnailFish> for FlatTree {
+    fn from(snailfish: SnailFish) -> Self {
+        let mut leaves = vec![];
+        // Push right before left so that leaves come off the stack in order.
+        let mut stack = vec![(0, snailfish)];
+        while let Some((depth, snailfish)) = stack.pop() {
+            match snailfish {
+                SnailFish::Literal(value) => leaves.push((depth, value)),
+                SnailFish::Pair(p) => {
+                    stack.push((depth + 1, p.right));
+                    stack.push((depth + 1, p.left));
+                }
+            }
+        }
+        Self { leaves }
+    }
+}
+
+impl FromStr for FlatTree {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<SnailFish>().map(Self::from)
+    }
+}
+
+impl From<&str> for FlatTree {
+    fn from(s: &str) -> Self {
+        FlatTree::from_str(s).unwrap_or_else(|e| panic!("invalid snailfish number {:?}: {}", s, e))
+    }
+}
+
+impl Display for FlatTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.leaves.is_empty() {
+            return Ok(());
+        }
+        self.fmt_node(0, &mut 0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tree;
+    use test_case::test_case;
+
+    #[test]
+    fn leaves() {
+        let tree = FlatTree::from("[[1,2],[[3,4],5]]");
+        assert_eq!(tree.leaves(), &[(2, 1), (2, 2), (3, 3), (3, 4), (2, 5)]);
+    }
+
+    #[test_case("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]")]
+    #[test_case("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]")]
+    #[test_case("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]")]
+    #[test_case(
+        "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+        "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"
+    )]
+    #[test_case("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]", "[[3,[2,[8,0]]],[9,[5,[7,0]]]]")]
+    fn test_explode(starting: &str, expected: &str) {
+        let mut tree = FlatTree::from(starting);
+        assert!(tree.explode());
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test_case(
+        "[[[[0,7],4],[15,[0,13]]],[1,1]]",
+        "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"
+    )]
+    #[test_case(
+        "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+        "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]"
+    )]
+    fn test_split(initial: &str, expected: &str) {
+        let mut tree = FlatTree::from(initial);
+        assert!(!tree.explode());
+        assert!(tree.split());
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test_case("[1,5]", 3 + 2 * 5)]
+    #[test_case("[[1,2],[[3,4],5]]", 143)]
+    #[test_case("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", 1384)]
+    #[test_case("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", 3488)]
+    #[test_case("9", 9)]
+    fn test_magnitude(raw: &str, expected: usize) {
+        assert_eq!(FlatTree::from(raw).magnitude(), expected);
+    }
+
+    #[test_case("[[[[4,3],4],4],[7,[[8,4],9]]]", "[1,1]")]
+    #[test_case(
+        "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]",
+        "[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]"
+    )]
+    #[test_case("[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]", "[2,9]")]
+    fn agrees_with_tree(op1: &str, op2: &str) {
+        let mut flat = FlatTree::from(op1);
+        flat.add(&FlatTree::from(op2));
+        let mut tree = Tree::from(op1);
+        tree.add(&Tree::from(op2));
+        assert_eq!(flat.to_string(), tree.as_string());
+        assert_eq!(SnailfishNumber::magnitude(&flat), tree.magnitude(0));
+    }
+}
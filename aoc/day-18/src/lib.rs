@@ -1,9 +1,24 @@
+mod dot;
+mod flat;
+mod ops;
+mod pairs;
 mod parse;
+#[cfg(test)]
+mod properties;
+mod rules;
+mod trace;
 mod tree;
 
+use std::{fmt::Display, iter::Sum};
+
 use aoc_core::Solution;
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use tree::*;
+pub use flat::*;
+pub use ops::*;
+pub use pairs::*;
+pub use parse::{Parse, ParseError, ParseErrorKind, DEFAULT_MAX_DEPTH};
+pub use rules::*;
+pub use trace::*;
+pub use tree::*;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum SnailFish {
@@ -17,48 +32,102 @@ pub struct Pair {
     pub right: SnailFish,
 }
 
+impl Display for SnailFish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnailFish::Pair(pair) => write!(f, "{}", pair),
+            SnailFish::Literal(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl Display for Pair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{},{}]", self.left, self.right)
+    }
+}
+
+/// What the solutions need out of a representation of snailfish numbers,
+/// so that they can run on either [`Tree`] or [`FlatTree`].
+pub trait SnailfishNumber: Clone + From<SnailFish> {
+    /// Add `rhs` to us and then reduce ourselves all the way with the given rules.
+    fn add_with(&mut self, rhs: &Self, rules: &ReductionRules);
+
+    /// Explode or split once with the given rules, if possible.
+    /// Return true if we did, false otherwise.
+    fn reduce_with(&mut self, rules: &ReductionRules) -> bool;
+
+    /// The magnitude of the whole number, weighed by the given rules.
+    fn magnitude_with(&self, rules: &ReductionRules) -> usize;
+
+    /// Repeatedly reduce with the given rules until we can't any more.
+    fn reduce_all_the_way_with(&mut self, rules: &ReductionRules) {
+        while self.reduce_with(rules) {}
+    }
+
+    /// Add `rhs` to us and then reduce ourselves all the way.
+    fn add(&mut self, rhs: &Self) {
+        self.add_with(rhs, &ReductionRules::PUZZLE)
+    }
+
+    /// Explode or split once, if possible. Return true if we did, false otherwise.
+    fn reduce(&mut self) -> bool {
+        self.reduce_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Repeatedly reduce until we can't any more.
+    fn reduce_all_the_way(&mut self) {
+        self.reduce_all_the_way_with(&ReductionRules::PUZZLE)
+    }
+
+    /// The magnitude of the whole number.
+    fn magnitude(&self) -> usize {
+        self.magnitude_with(&ReductionRules::PUZZLE)
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Solver {}
 
-pub fn build_trees(s: &str) -> Vec<Tree> {
-    s.lines().map(Tree::from).collect()
+/// Parse every line of the input as a [`Tree`].
+pub fn build_trees(s: &str) -> Result<Vec<Tree>, ParseError> {
+    parse::parse_lines(s)
+}
+
+/// Parse every line of the input as a snailfish number.
+pub fn build_numbers<N: SnailfishNumber>(s: &str) -> Result<Vec<N>, ParseError> {
+    parse::parse_lines(s)
 }
 
 /// Add the given list of snailfish numbers while reducing
 /// them as many times as required. Return the magnitude of
 /// the final snailfish number that stands.
 pub fn solve_part1(s: &str) -> usize {
-    let trees = build_trees(s);
-    let mut acc_tree = trees.first().unwrap().clone();
-    trees.iter().skip(1).for_each(|tree| {
-        acc_tree.add(tree);
-    });
-    acc_tree.magnitude(0)
+    solve_part1_with::<FlatTree>(s)
+}
+
+/// Solve part 1 using the given representation of snailfish numbers.
+pub fn solve_part1_with<N>(s: &str) -> usize
+where
+    N: SnailfishNumber,
+    Result<N, EmptySum>: Sum<N>,
+{
+    let numbers = build_numbers::<N>(s).unwrap_or_else(|e| panic!("{}", e));
+    let sum: Result<N, EmptySum> = numbers.into_iter().sum();
+    sum.expect("The input should have at least one snailfish number.")
+        .magnitude()
 }
 
 /// Find the maximum magnitude amongst any sum of two snailfish numbers in the given input.
 pub fn solve_part2(s: &str) -> usize {
-    let trees = build_trees(s);
-    let mut tree_pair: Vec<(Tree, Tree)> = vec![];
-    for x in 0..trees.len() {
-        for y in 0..trees.len() {
-            if x == y {
-                continue;
-            }
-            tree_pair.push((trees.get(x).unwrap().clone(), trees.get(y).unwrap().clone()));
-        }
-    }
+    solve_part2_with::<FlatTree>(s)
+}
 
-    tree_pair
-        // rayon goes brrrr.
-        .par_iter()
-        .map(|(t1, t2)| {
-            let mut acc_tree = t1.clone();
-            acc_tree.add(t2);
-            acc_tree.magnitude(0)
-        })
-        .max()
-        .unwrap()
+/// Solve part 2 using the given representation of snailfish numbers.
+pub fn solve_part2_with<N: SnailfishNumber + Send + Sync>(s: &str) -> usize {
+    let numbers = build_numbers::<N>(s).unwrap_or_else(|e| panic!("{}", e));
+    max_pair_magnitude_pruned(&numbers, &ReductionRules::PUZZLE)
+        .expect("The input should have at least two snailfish numbers.")
 }
 
 impl Solution for Solver {
@@ -317,6 +386,19 @@ mod tests {
         assert_eq!(solution, highest_magnitude);
     }
 
+    #[test]
+    fn representations_agree() {
+        let input = include_str!("input.txt");
+        assert_eq!(
+            solve_part1_with::<Tree>(input),
+            solve_part1_with::<FlatTree>(input)
+        );
+        assert_eq!(
+            solve_part2_with::<Tree>(input),
+            solve_part2_with::<FlatTree>(input)
+        );
+    }
+
     #[test_case(
         "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
 [7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
@@ -346,7 +428,7 @@ mod tests {
         final_sum: &str,
         magnitude: usize,
     ) {
-        let trees = build_trees(homework_assignment);
+        let trees = build_trees(homework_assignment).unwrap();
         let mut acc_tree = trees.first().unwrap().clone();
         trees.iter().skip(1).enumerate().for_each(|(index, tree)| {
             acc_tree.add(tree);
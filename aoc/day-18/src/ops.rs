@@ -0,0 +1,161 @@
+use std::{
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+use crate::{FlatTree, SnailfishNumber, Tree};
+
+/// There's nothing to add up.
+///
+/// Snailfish numbers have no zero to fall back on,
+/// so the sum of an empty list is an error instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptySum;
+
+impl Display for EmptySum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "can't add up an empty list of snailfish numbers")
+    }
+}
+
+impl std::error::Error for EmptySum {}
+
+/// Implement the arithmetic operators for a representation of snailfish numbers,
+/// all of which add and then reduce all the way with the puzzle rules.
+///
+/// Keep in mind that snailfish addition isn't associative, so sums have to be taken
+/// in order. Parallel iterators are fine for independent additions (like every pair
+/// in part 2) but not for splitting up a single sum.
+macro_rules! impl_ops {
+    ($number:ty) => {
+        impl AddAssign<&$number> for $number {
+            fn add_assign(&mut self, rhs: &$number) {
+                SnailfishNumber::add(self, rhs);
+            }
+        }
+
+        impl AddAssign for $number {
+            fn add_assign(&mut self, rhs: $number) {
+                *self += &rhs;
+            }
+        }
+
+        impl Add<&$number> for $number {
+            type Output = $number;
+            fn add(mut self, rhs: &$number) -> $number {
+                self += rhs;
+                self
+            }
+        }
+
+        impl Add for $number {
+            type Output = $number;
+            fn add(self, rhs: $number) -> $number {
+                self + &rhs
+            }
+        }
+
+        impl Add for &$number {
+            type Output = $number;
+            fn add(self, rhs: &$number) -> $number {
+                self.clone() + rhs
+            }
+        }
+
+        impl Sum<$number> for Result<$number, EmptySum> {
+            /// Add up the numbers from left to right.
+            fn sum<I: Iterator<Item = $number>>(mut iter: I) -> Self {
+                let first = iter.next().ok_or(EmptySum)?;
+                Ok(iter.fold(first, |sum, number| sum + &number))
+            }
+        }
+
+        impl<'a> Sum<&'a $number> for Result<$number, EmptySum> {
+            /// Add up the numbers from left to right.
+            fn sum<I: Iterator<Item = &'a $number>>(iter: I) -> Self {
+                iter.cloned().sum()
+            }
+        }
+    };
+}
+
+impl_ops!(Tree);
+impl_ops!(FlatTree);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_trees, solve_part2};
+    use rayon::prelude::*;
+
+    const HOMEWORK: &str = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
+[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
+[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]
+[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]
+[7,[5,[[3,8],[1,4]]]]
+[[2,[2,2]],[8,[8,1]]]
+[2,9]
+[1,[[[9,3],9],[[9,0],[0,7]]]]
+[[[5,[7,4]],7],1]
+[[[[4,2],2],6],[8,7]]";
+
+    const TOTAL: &str = "[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]";
+
+    #[test]
+    fn operators() {
+        let a = Tree::from("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let b = Tree::from("[1,1]");
+        let expected = "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]";
+
+        assert_eq!((&a + &b).as_string(), expected);
+        assert_eq!((a.clone() + &b).as_string(), expected);
+        assert_eq!((a.clone() + b.clone()).as_string(), expected);
+
+        let mut c = a.clone();
+        c += &b;
+        assert_eq!(c.as_string(), expected);
+        let mut c = a;
+        c += b;
+        assert_eq!(c.as_string(), expected);
+    }
+
+    #[test]
+    fn sums() {
+        let trees = build_trees(HOMEWORK).unwrap();
+        let by_reference: Result<Tree, EmptySum> = trees.iter().sum();
+        assert_eq!(by_reference.unwrap().as_string(), TOTAL);
+        let by_value: Result<Tree, EmptySum> = trees.into_iter().sum();
+        assert_eq!(by_value.unwrap().as_string(), TOTAL);
+
+        let flat = HOMEWORK.lines().map(FlatTree::from).collect::<Vec<_>>();
+        let sum: Result<FlatTree, _> = flat.iter().sum();
+        assert_eq!(sum.unwrap().to_string(), TOTAL);
+    }
+
+    #[test]
+    fn single_and_empty_sums() {
+        let one = [Tree::from("[[[[[9,8],1],2],3],4]")];
+        // A lone number isn't added to anything, so it isn't reduced either.
+        let sum: Result<Tree, EmptySum> = one.iter().sum();
+        assert_eq!(sum.unwrap().as_string(), "[[[[[9,8],1],2],3],4]");
+
+        let none: Vec<Tree> = vec![];
+        let sum: Result<Tree, EmptySum> = none.iter().sum();
+        assert!(matches!(sum, Err(EmptySum)));
+        let sum: Result<FlatTree, EmptySum> = std::iter::empty::<FlatTree>().sum();
+        assert_eq!(sum, Err(EmptySum));
+    }
+
+    #[test]
+    fn parallel_pairs() {
+        let trees = build_trees(HOMEWORK).unwrap();
+        let best = (0..trees.len())
+            .into_par_iter()
+            .flat_map(|i| (0..trees.len()).into_par_iter().map(move |j| (i, j)))
+            .filter(|(i, j)| i != j)
+            .map(|(i, j)| (&trees[i] + &trees[j]).magnitude(0))
+            .max();
+        assert_eq!(best, Some(solve_part2(HOMEWORK)));
+    }
+}
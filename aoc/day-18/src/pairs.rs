@@ -0,0 +1,178 @@
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rayon::prelude::*;
+
+use crate::{ReductionRules, SnailfishNumber};
+
+/// The largest magnitude of the sum of two different numbers out of `numbers`,
+/// added in either order, or `None` if there aren't two numbers to add.
+///
+/// The pairs are never collected up front: every thread walks its own share of
+/// index pairs and adds them up in a scratch number that it reuses throughout.
+pub fn max_pair_magnitude<N>(numbers: &[N], rules: &ReductionRules) -> Option<usize>
+where
+    N: SnailfishNumber + Send + Sync,
+{
+    if numbers.len() < 2 {
+        return None;
+    }
+    let n = numbers.len();
+    (0..n)
+        .into_par_iter()
+        .flat_map_iter(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+        .map_init(
+            || numbers[0].clone(),
+            |scratch, (i, j)| pair_magnitude(scratch, &numbers[i], &numbers[j], rules),
+        )
+        .max()
+}
+
+/// Like [`max_pair_magnitude`], but skips the pairs whose [`magnitude_bound`]
+/// says they can't beat the best magnitude found so far.
+///
+/// Numbers are tried in decreasing order of the total of their regular numbers,
+/// so once one pair can be skipped so can the rest of the pairs with the same left number.
+pub fn max_pair_magnitude_pruned<N>(numbers: &[N], rules: &ReductionRules) -> Option<usize>
+where
+    N: SnailfishNumber + Send + Sync,
+{
+    if numbers.len() < 2 {
+        return None;
+    }
+    let totals = numbers
+        .par_iter()
+        .map(|number| regular_total(number, rules))
+        .collect::<Vec<_>>();
+    let mut order = (0..numbers.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| Reverse(totals[i]));
+
+    let best = AtomicUsize::new(0);
+    order.par_iter().for_each_init(
+        || numbers[0].clone(),
+        |scratch, &i| {
+            for &j in order.iter().filter(|&&j| j != i) {
+                let bound = magnitude_bound(totals[i] + totals[j], rules);
+                if bound <= best.load(Ordering::Relaxed) {
+                    break;
+                }
+                let magnitude = pair_magnitude(scratch, &numbers[i], &numbers[j], rules);
+                best.fetch_max(magnitude, Ordering::Relaxed);
+            }
+        },
+    );
+    Some(best.into_inner())
+}
+
+/// An upper bound on the magnitude of any reduced sum
+/// whose regular numbers added up to `total` before reducing.
+///
+/// Splitting keeps the total of the regular numbers the same and exploding can only
+/// lose the halves that fall off either end, so the reduced sum can't have a larger total.
+/// Once reduced, no regular number is nested inside more than `rules.explode_depth()` pairs,
+/// which caps how much each of them can be weighed, and none of them is big enough to split,
+/// which caps the magnitude of the sum as a whole.
+pub fn magnitude_bound(total: usize, rules: &ReductionRules) -> usize {
+    let (left, right) = rules.magnitude_weights();
+    let depth = rules.explode_depth() as u32;
+    let heaviest_leaf = left.max(right).saturating_pow(depth);
+    let largest_number = (left + right)
+        .saturating_pow(depth)
+        .saturating_mul(rules.split_threshold().saturating_sub(1));
+    total.saturating_mul(heaviest_leaf).min(largest_number)
+}
+
+/// Add `right` to `left` in `scratch` and return the magnitude of the sum.
+fn pair_magnitude<N: SnailfishNumber>(
+    scratch: &mut N,
+    left: &N,
+    right: &N,
+    rules: &ReductionRules,
+) -> usize {
+    scratch.clone_from(left);
+    scratch.add_with(right, rules);
+    scratch.magnitude_with(rules)
+}
+
+/// The total of the regular numbers in `number`, i.e. its magnitude when both weights are 1.
+fn regular_total<N: SnailfishNumber>(number: &N, rules: &ReductionRules) -> usize {
+    let unweighted = rules.with_magnitude_weights((1, 1));
+    number.magnitude_with(&unweighted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_numbers, FlatTree, SplitRounding, Tree};
+    use test_case::test_case;
+
+    const EXAMPLE: &str = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
+
+    #[test_case(EXAMPLE, 3993)]
+    #[test_case(include_str!("input.txt"), 4721)]
+    fn pruning_agrees(input: &str, expected: usize) {
+        let rules = ReductionRules::PUZZLE;
+        let flat = build_numbers::<FlatTree>(input).unwrap();
+        assert_eq!(max_pair_magnitude(&flat, &rules), Some(expected));
+        assert_eq!(max_pair_magnitude_pruned(&flat, &rules), Some(expected));
+        let trees = build_numbers::<Tree>(input).unwrap();
+        assert_eq!(max_pair_magnitude_pruned(&trees, &rules), Some(expected));
+    }
+
+    #[test]
+    fn pruning_agrees_with_other_rules() {
+        let rules = ReductionRules::new(3, 7)
+            .unwrap()
+            .with_split_rounding(SplitRounding::LeftUp)
+            .with_magnitude_weights((2, 5));
+        let flat = build_numbers::<FlatTree>(EXAMPLE).unwrap();
+        assert_eq!(
+            max_pair_magnitude(&flat, &rules),
+            max_pair_magnitude_pruned(&flat, &rules)
+        );
+    }
+
+    #[test]
+    fn bounds_hold() {
+        let rules = ReductionRules::PUZZLE;
+        let numbers = build_numbers::<FlatTree>(EXAMPLE).unwrap();
+        for left in &numbers {
+            for right in &numbers {
+                let total = regular_total(left, &rules) + regular_total(right, &rules);
+                assert!((left + right).magnitude() <= magnitude_bound(total, &rules));
+            }
+        }
+        assert_eq!(magnitude_bound(1000, &rules), 9 * 5usize.pow(4));
+        assert_eq!(magnitude_bound(10, &rules), 10 * 3usize.pow(4));
+    }
+
+    #[test]
+    fn too_few_numbers() {
+        let one = [FlatTree::from("[1,2]")];
+        assert_eq!(max_pair_magnitude(&one, &ReductionRules::PUZZLE), None);
+        assert_eq!(
+            max_pair_magnitude_pruned(&one[..0], &ReductionRules::PUZZLE),
+            None
+        );
+
+        // A number isn't added to itself, but an equal copy of it is fair game.
+        let two = [FlatTree::from("[1,2]"), FlatTree::from("[1,2]")];
+        let sum = (&two[0] + &two[1]).magnitude();
+        assert_eq!(max_pair_magnitude(&two, &ReductionRules::PUZZLE), Some(sum));
+        assert_eq!(
+            max_pair_magnitude_pruned(&two, &ReductionRules::PUZZLE),
+            Some(sum)
+        );
+    }
+}
@@ -1,14 +1,77 @@
+use std::{fmt::Display, str::FromStr};
+
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, space0},
-    combinator::map,
+    character::complete::{digit1, multispace0, space0},
+    combinator::{map, map_res},
     sequence::tuple,
     *,
 };
 
 use crate::{Pair, SnailFish};
 
+/// How many pairs a regular number may be nested inside unless told otherwise.
+///
+/// Reduced numbers never go past 4, but [`Tree`](crate::Tree) needs `2^depth` slots,
+/// so anything much deeper than this is more likely a mistake than a snailfish number.
+pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+/// What went wrong when parsing a snailfish number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Something other than `expected` was found, or nothing at all if `found` is `None`.
+    Unexpected {
+        expected: &'static str,
+        found: Option<char>,
+    },
+    /// The regular numbers of a pair are nested inside more than `max_depth` pairs.
+    TooDeep { max_depth: usize },
+    /// A regular number doesn't fit in a `usize`.
+    TooLarge,
+}
+
+/// A snailfish number that couldn't be parsed, along with where the problem is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The (1-based) line the problem is on, when parsing a whole input.
+    pub line: Option<usize>,
+    /// The byte offset of the problem within its line.
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}, ", line)?;
+        }
+        write!(f, "byte {}: ", self.offset)?;
+        match &self.kind {
+            ParseErrorKind::Unexpected {
+                expected,
+                found: Some(found),
+            } => write!(f, "expected {} but found {:?}", expected, found),
+            ParseErrorKind::Unexpected {
+                expected,
+                found: None,
+            } => write!(f, "expected {} but the input ended", expected),
+            ParseErrorKind::TooDeep { max_depth } => {
+                write!(
+                    f,
+                    "regular numbers nested inside more than {} pairs",
+                    max_depth
+                )
+            }
+            ParseErrorKind::TooLarge => write!(f, "regular number is too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A nom parser for snailfish numbers that hands back whatever input follows them,
+/// for embedding in bigger grammars. Use [`FromStr`] to parse a whole number instead.
 pub trait Parse {
     fn parse(s: &str) -> IResult<&str, Self>
     where
@@ -36,18 +99,123 @@ impl Parse for Pair {
 impl Parse for SnailFish {
     fn parse(s: &str) -> IResult<&str, Self> {
         alt((
-            map(digit1, |s: &str| {
-                SnailFish::Literal(s.parse::<usize>().unwrap())
-            }),
+            map(map_res(digit1, str::parse::<usize>), SnailFish::Literal),
             map(Pair::parse, |p| SnailFish::Pair(Box::new(p))),
         ))(s)
     }
 }
 
+impl SnailFish {
+    /// Parse exactly one snailfish number, with nothing but whitespace around it,
+    /// whose regular numbers are nested inside at most `max_depth` pairs.
+    ///
+    /// Like [`Parse`], only spaces and tabs are allowed inside the number itself.
+    pub fn parse_with_max_depth(s: &str, max_depth: usize) -> Result<Self, ParseError> {
+        let strict = Strict {
+            input: s,
+            max_depth,
+        };
+        let (rest, _) = multispace0::<_, error::Error<_>>(s).unwrap();
+        let (rest, snailfish) = strict.snailfish(rest, 0)?;
+        let (rest, _) = multispace0::<_, error::Error<_>>(rest).unwrap();
+        match rest.is_empty() {
+            true => Ok(snailfish),
+            false => Err(strict.unexpected(rest, "the end of the number")),
+        }
+    }
+}
+
+impl FromStr for SnailFish {
+    type Err = ParseError;
+
+    /// Parse exactly one snailfish number nested at most [`DEFAULT_MAX_DEPTH`] pairs deep.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with_max_depth(s, DEFAULT_MAX_DEPTH)
+    }
+}
+
+/// Parse every line of `s` as a snailfish number, noting the line number of any error.
+pub(crate) fn parse_lines<T: From<SnailFish>>(s: &str) -> Result<Vec<T>, ParseError> {
+    s.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            line.parse::<SnailFish>()
+                .map(T::from)
+                .map_err(|e| ParseError {
+                    line: Some(index + 1),
+                    ..e
+                })
+        })
+        .collect()
+}
+
+/// A parser that, unlike [`Parse`], keeps track of where it is and how deep it went,
+/// so that its errors can say what went wrong where.
+struct Strict<'a> {
+    input: &'a str,
+    max_depth: usize,
+}
+
+type StrictResult<'a, T> = Result<(&'a str, T), ParseError>;
+
+impl<'a> Strict<'a> {
+    fn error(&self, rest: &str, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: None,
+            offset: self.input.len() - rest.len(),
+            kind,
+        }
+    }
+
+    fn unexpected(&self, rest: &str, expected: &'static str) -> ParseError {
+        let found = rest.chars().next();
+        self.error(rest, ParseErrorKind::Unexpected { expected, found })
+    }
+
+    fn token(
+        &self,
+        s: &'a str,
+        token: &'static str,
+        expected: &'static str,
+    ) -> StrictResult<'a, ()> {
+        let (s, _) = space0::<_, error::Error<_>>(s).unwrap();
+        tag::<_, _, error::Error<_>>(token)(s)
+            .map(|(rest, _)| (rest, ()))
+            .map_err(|_| self.unexpected(s, expected))
+    }
+
+    /// Parse a regular number or a pair nested inside `depth` pairs.
+    fn snailfish(&self, s: &'a str, depth: usize) -> StrictResult<'a, SnailFish> {
+        let (s, _) = space0::<_, error::Error<_>>(s).unwrap();
+        if let Ok((rest, digits)) = digit1::<_, error::Error<_>>(s) {
+            let value = digits
+                .parse()
+                .map_err(|_| self.error(s, ParseErrorKind::TooLarge))?;
+            return Ok((rest, SnailFish::Literal(value)));
+        }
+        if !s.starts_with('[') {
+            return Err(self.unexpected(s, "a regular number or `[`"));
+        }
+        // The regular numbers of this pair would be nested inside one more pair than it is.
+        if depth == self.max_depth {
+            let max_depth = self.max_depth;
+            return Err(self.error(s, ParseErrorKind::TooDeep { max_depth }));
+        }
+
+        let (s, _) = self.token(s, "[", "`[`")?;
+        let (s, left) = self.snailfish(s, depth + 1)?;
+        let (s, _) = self.token(s, ",", "`,`")?;
+        let (s, right) = self.snailfish(s, depth + 1)?;
+        let (s, _) = self.token(s, "]", "`]`")?;
+        Ok((s, SnailFish::Pair(Box::new(Pair { left, right }))))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::*;
+    use test_case::test_case;
 
     #[test]
     fn simple_parse() {
@@ -76,4 +244,115 @@ mod tests {
         let (_, p) = Pair::parse(raw).unwrap();
         assert_eq!(p.right, SnailFish::Literal(4));
     }
+
+    #[test_case("[1,2]")]
+    #[test_case(" [ [1 , 2],3 ] ")]
+    #[test_case("[[[[[9,8],1],2],3],4]")]
+    #[test_case("7")]
+    #[test_case("\n\t[1,\t2]\r\n")]
+    fn strict_parse(raw: &str) {
+        let (_, expected) = SnailFish::parse(raw.trim()).unwrap();
+        assert_eq!(raw.parse::<SnailFish>(), Ok(expected));
+    }
+
+    #[test_case("[1,2]]", 5, "the end of the number", Some(']'))]
+    #[test_case("[1,2] x", 6, "the end of the number", Some('x'))]
+    #[test_case("[1;2]", 2, "`,`", Some(';'))]
+    #[test_case("[1,2", 4, "`]`", None)]
+    #[test_case("[[1,2],]", 7, "a regular number or `[`", Some(']'))]
+    #[test_case("", 0, "a regular number or `[`", None)]
+    #[test_case("[-1,2]", 1, "a regular number or `[`", Some('-'))]
+    #[test_case("[1,\n2]", 3, "a regular number or `[`", Some('\n'))]
+    #[test_case("[1\n,2]", 2, "`,`", Some('\n'))]
+    fn unexpected(raw: &str, offset: usize, expected: &'static str, found: Option<char>) {
+        let error = raw.parse::<SnailFish>().unwrap_err();
+        assert_eq!(error.line, None);
+        assert_eq!(error.offset, offset);
+        assert_eq!(error.kind, ParseErrorKind::Unexpected { expected, found });
+    }
+
+    #[test]
+    fn error_messages() {
+        let error = "[1,2]]".parse::<SnailFish>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "byte 5: expected the end of the number but found ']'"
+        );
+        let error = "[1,".parse::<SnailFish>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "byte 3: expected a regular number or `[` but the input ended"
+        );
+        let error = "[99999999999999999999999,1]"
+            .parse::<SnailFish>()
+            .unwrap_err();
+        assert_eq!(error.to_string(), "byte 1: regular number is too large");
+    }
+
+    #[test_case(5, true)]
+    #[test_case(4, false)]
+    fn max_depth(max_depth: usize, ok: bool) {
+        let raw = "[[[[[9,8],1],2],3],4]";
+        let parsed = SnailFish::parse_with_max_depth(raw, max_depth);
+        match ok {
+            true => assert!(parsed.is_ok()),
+            false => assert_eq!(
+                parsed.unwrap_err(),
+                ParseError {
+                    line: None,
+                    offset: 4,
+                    kind: ParseErrorKind::TooDeep { max_depth: 4 }
+                }
+            ),
+        }
+    }
+
+    #[test]
+    fn default_max_depth() {
+        let deep = "[".repeat(DEFAULT_MAX_DEPTH + 1) + "1" + &",2]".repeat(DEFAULT_MAX_DEPTH + 1);
+        let error = deep.parse::<SnailFish>().unwrap_err();
+        assert_eq!(error.offset, DEFAULT_MAX_DEPTH);
+        assert_eq!(
+            error.to_string(),
+            "byte 16: regular numbers nested inside more than 16 pairs"
+        );
+
+        // The regular numbers of the innermost pair are nested inside exactly 16 pairs.
+        let deepest = "[".repeat(DEFAULT_MAX_DEPTH) + "1" + &",2]".repeat(DEFAULT_MAX_DEPTH);
+        assert!(deepest.parse::<SnailFish>().is_ok());
+    }
+
+    #[test_case(0, "7", None)]
+    #[test_case(0, "[1,2]", Some(0))]
+    #[test_case(1, "[1,2]", None)]
+    #[test_case(1, "[[1,2],3]", Some(1))]
+    #[test_case(2, "[[1,2],[3,[4,5]]]", Some(10))]
+    fn max_depth_is_inclusive(max_depth: usize, raw: &str, offset: Option<usize>) {
+        let parsed = SnailFish::parse_with_max_depth(raw, max_depth);
+        assert_eq!(parsed.err().map(|e| e.offset), offset);
+    }
+
+    #[test]
+    fn legacy_parser_rejects_huge_numbers() {
+        assert!(SnailFish::parse("99999999999999999999999").is_err());
+        assert!(Pair::parse("[1,99999999999999999999999]").is_err());
+        assert_eq!(
+            SnailFish::parse("18446744073709551615"),
+            Ok(("", SnailFish::Literal(usize::MAX)))
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let numbers = parse_lines::<Tree>("[1,2]\n[[3,4],5]").unwrap();
+        assert_eq!(numbers.len(), 2);
+
+        let error = parse_lines::<Tree>("[1,2]\n[[3,4],5]\n[6,7]]\n[8,9]").unwrap_err();
+        assert_eq!(error.line, Some(3));
+        assert_eq!(error.offset, 5);
+        assert_eq!(
+            error.to_string(),
+            "line 3, byte 5: expected the end of the number but found ']'"
+        );
+    }
 }
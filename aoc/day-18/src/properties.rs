@@ -0,0 +1,140 @@
+//! Properties that should hold for any snailfish number, not just the ones in the examples.
+
+use proptest::prelude::*;
+
+use crate::{
+    tree::depth_of, FlatTree, Pair, ReductionRules, SnailFish, SnailfishNumber, SplitRounding, Tree,
+};
+
+/// Random snailfish numbers with regular numbers up to `max_value`,
+/// nested inside at most `max_depth` pairs.
+fn snailfish(max_depth: u32, max_value: usize) -> impl Strategy<Value = SnailFish> {
+    let literal = (0..=max_value).prop_map(SnailFish::Literal);
+    literal.prop_recursive(max_depth, 1 << max_depth, 2, |inner| {
+        (inner.clone(), inner)
+            .prop_map(|(left, right)| SnailFish::Pair(Box::new(Pair { left, right })))
+    })
+}
+
+/// Random numbers like the ones in the puzzle input, which are already reduced.
+fn reduced_snailfish() -> impl Strategy<Value = SnailFish> {
+    snailfish(4, 9)
+}
+
+fn rules() -> impl Strategy<Value = ReductionRules> {
+    (
+        1..=5usize,
+        2..=12usize,
+        prop_oneof![Just(SplitRounding::LeftDown), Just(SplitRounding::LeftUp)],
+        (0..=4usize, 0..=4usize),
+    )
+        .prop_map(
+            |(explode_depth, split_threshold, split_rounding, magnitude_weights)| {
+                ReductionRules::new(explode_depth, split_threshold)
+                    .unwrap()
+                    .with_split_rounding(split_rounding)
+                    .with_magnitude_weights(magnitude_weights)
+            },
+        )
+}
+
+/// Check that nothing in `flat` can explode or split any more under `rules`.
+fn assert_reduced(flat: &FlatTree, rules: &ReductionRules) -> Result<(), TestCaseError> {
+    for &(depth, value) in flat.leaves() {
+        prop_assert!(depth <= rules.explode_depth(), "{} is too deep", flat);
+        prop_assert!(value < rules.split_threshold(), "{} is too large", flat);
+    }
+    Ok(())
+}
+
+/// Like [`assert_reduced`], but checking the regular numbers where they sit in `tree`.
+fn assert_tree_reduced(tree: &Tree, rules: &ReductionRules) -> Result<(), TestCaseError> {
+    for (index, value) in tree.regular_numbers() {
+        prop_assert!(
+            depth_of(index) <= rules.explode_depth(),
+            "{} is too deep",
+            tree
+        );
+        prop_assert!(value < rules.split_threshold(), "{} is too large", tree);
+    }
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn reducing_leaves_nothing_to_explode_or_split(number in snailfish(7, 30)) {
+        let raw = number.to_string();
+        let mut flat = FlatTree::from(number);
+        flat.reduce_all_the_way();
+        assert_reduced(&flat, &ReductionRules::PUZZLE)?;
+
+        let mut tree = Tree::from(raw.as_str());
+        tree.reduce_all_the_way();
+        assert_tree_reduced(&tree, &ReductionRules::PUZZLE)?;
+        prop_assert_eq!(tree.to_string(), flat.to_string());
+    }
+
+    #[test]
+    fn sums_are_reduced(left in reduced_snailfish(), right in reduced_snailfish()) {
+        let (left, right) = (left.to_string(), right.to_string());
+        let flat = FlatTree::from(left.as_str()) + FlatTree::from(right.as_str());
+        assert_reduced(&flat, &ReductionRules::PUZZLE)?;
+
+        let tree = Tree::from(left.as_str()) + Tree::from(right.as_str());
+        assert_tree_reduced(&tree, &ReductionRules::PUZZLE)?;
+        prop_assert_eq!(tree.to_string(), flat.to_string());
+        prop_assert_eq!(tree.magnitude(0), SnailfishNumber::magnitude(&flat));
+    }
+
+    #[test]
+    fn representations_agree_step_by_step(number in snailfish(6, 20)) {
+        let raw = number.to_string();
+        let mut tree = Tree::from(raw.as_str());
+        let mut flat = FlatTree::from(number);
+        loop {
+            let reduced = tree.reduce();
+            prop_assert_eq!(reduced, flat.reduce());
+            prop_assert_eq!(tree.to_string(), flat.to_string());
+            if !reduced {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn representations_agree_on_any_rules(
+        left in snailfish(4, 12),
+        right in snailfish(4, 12),
+        rules in rules(),
+    ) {
+        let (left, right) = (left.to_string(), right.to_string());
+        let mut tree = Tree::from(left.as_str());
+        tree.add_with(&Tree::from(right.as_str()), &rules);
+        let mut flat = FlatTree::from(left.as_str());
+        flat.add_with(&FlatTree::from(right.as_str()), &rules);
+
+        assert_reduced(&flat, &rules)?;
+        assert_tree_reduced(&tree, &rules)?;
+        prop_assert_eq!(tree.to_string(), flat.to_string());
+        prop_assert_eq!(tree.magnitude_with(0, &rules), flat.magnitude_with(&rules));
+    }
+
+    #[test]
+    fn magnitude_survives_a_round_trip(number in snailfish(6, 50), rules in rules()) {
+        let raw = number.to_string();
+        let parsed = raw.parse::<SnailFish>();
+        prop_assert_eq!(parsed.as_ref(), Ok(&number));
+        // Built straight from the generated number, so it never goes through `Display`.
+        let flat = FlatTree::from(number);
+
+        let tree = Tree::from(raw.as_str());
+        let reparsed = tree.to_string().parse::<Tree>().unwrap();
+        prop_assert_eq!(&reparsed, &tree);
+        prop_assert_eq!(reparsed.magnitude(0), SnailfishNumber::magnitude(&flat));
+        prop_assert_eq!(reparsed.magnitude_with(0, &rules), flat.magnitude_with(&rules));
+
+        let reparsed = flat.to_string().parse::<FlatTree>().unwrap();
+        prop_assert_eq!(reparsed.leaves(), flat.leaves());
+        prop_assert_eq!(reparsed.magnitude_with(&rules), flat.magnitude_with(&rules));
+    }
+}
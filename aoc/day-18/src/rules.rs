@@ -0,0 +1,227 @@
+use std::fmt::Display;
+
+/// Which way to round the halves of an odd number when splitting it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SplitRounding {
+    /// The left half is rounded down and the right half up, as in the puzzle.
+    #[default]
+    LeftDown,
+    /// The left half is rounded up and the right half down.
+    LeftUp,
+}
+
+/// The knobs that decide how snailfish numbers reduce and what their magnitude is.
+///
+/// The defaults are the ones from the puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReductionRules {
+    explode_depth: usize,
+    split_threshold: usize,
+    split_rounding: SplitRounding,
+    magnitude_weights: (usize, usize),
+}
+
+/// Rules that would break reduction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RulesError {
+    /// Pairs have to be nested inside at least one pair to explode,
+    /// since there's nowhere for the outermost pair to explode to.
+    ExplodeDepthTooSmall(usize),
+    /// Regular numbers have to be at least 2 to split,
+    /// or else splitting a 1 gives back a 1 and reduction never ends.
+    SplitThresholdTooSmall(usize),
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::ExplodeDepthTooSmall(depth) => {
+                write!(f, "explode depth {} is less than 1", depth)
+            }
+            RulesError::SplitThresholdTooSmall(threshold) => {
+                write!(f, "split threshold {} is less than 2", threshold)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl ReductionRules {
+    pub const PUZZLE: Self = Self {
+        explode_depth: 4,
+        split_threshold: 10,
+        split_rounding: SplitRounding::LeftDown,
+        magnitude_weights: (3, 2),
+    };
+
+    /// The puzzle rules, but exploding and splitting at the given depth and threshold.
+    ///
+    /// Fails unless `explode_depth` is at least 1 and `split_threshold` at least 2.
+    pub fn new(explode_depth: usize, split_threshold: usize) -> Result<Self, RulesError> {
+        if explode_depth < 1 {
+            return Err(RulesError::ExplodeDepthTooSmall(explode_depth));
+        }
+        if split_threshold < 2 {
+            return Err(RulesError::SplitThresholdTooSmall(split_threshold));
+        }
+        Ok(Self {
+            explode_depth,
+            split_threshold,
+            ..Self::PUZZLE
+        })
+    }
+
+    pub fn with_split_rounding(self, split_rounding: SplitRounding) -> Self {
+        Self {
+            split_rounding,
+            ..self
+        }
+    }
+
+    pub fn with_magnitude_weights(self, magnitude_weights: (usize, usize)) -> Self {
+        Self {
+            magnitude_weights,
+            ..self
+        }
+    }
+
+    /// Pairs of regular numbers nested inside more than this many pairs explode.
+    pub fn explode_depth(&self) -> usize {
+        self.explode_depth
+    }
+
+    /// Regular numbers at least this large split.
+    pub fn split_threshold(&self) -> usize {
+        self.split_threshold
+    }
+
+    pub fn split_rounding(&self) -> SplitRounding {
+        self.split_rounding
+    }
+
+    /// The weights of the left and right magnitudes in the magnitude of a pair.
+    pub fn magnitude_weights(&self) -> (usize, usize) {
+        self.magnitude_weights
+    }
+
+    /// Split `value` into the left and right regular numbers of a pair.
+    pub fn halves(&self, value: usize) -> (usize, usize) {
+        match self.split_rounding {
+            SplitRounding::LeftDown => (value / 2, value.div_ceil(2)),
+            SplitRounding::LeftUp => (value.div_ceil(2), value / 2),
+        }
+    }
+
+    /// The magnitude of a pair, given the magnitudes of its left and right halves.
+    pub fn magnitude(&self, left: usize, right: usize) -> usize {
+        let (left_weight, right_weight) = self.magnitude_weights;
+        left_weight * left + right_weight * right
+    }
+}
+
+impl Default for ReductionRules {
+    fn default() -> Self {
+        Self::PUZZLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FlatTree, SnailfishNumber, Tree};
+    use test_case::test_case;
+
+    /// Reduce `raw` all the way with both representations, checking that they agree.
+    fn reduce(raw: &str, rules: &ReductionRules) -> String {
+        let mut tree = Tree::from(raw);
+        tree.reduce_all_the_way_with(rules);
+        let mut flat = FlatTree::from(raw);
+        flat.reduce_all_the_way_with(rules);
+        assert_eq!(tree.as_string(), flat.to_string());
+        flat.to_string()
+    }
+
+    #[test]
+    fn puzzle_defaults() {
+        let rules = ReductionRules::default();
+        assert_eq!(rules, ReductionRules::PUZZLE);
+        assert_eq!(rules.halves(11), (5, 6));
+        assert_eq!(rules.halves(10), (5, 5));
+        assert_eq!(rules.magnitude(9, 1), 29);
+        assert_eq!(
+            reduce("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]", &rules),
+            "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"
+        );
+    }
+
+    #[test_case(1, "[[1,[2,3]],4]", "[0,7]")]
+    #[test_case(2, "[[1,[2,3]],4]", "[[3,0],7]")]
+    #[test_case(3, "[[1,[2,3]],4]", "[[1,[2,3]],4]")]
+    #[test_case(2, "[[[[1,2],3],4],5]", "[[0,9],5]")]
+    #[test_case(3, "[[[[[1,2],3],4],5],6]", "[[[0,9],5],6]")]
+    fn explode_depths(explode_depth: usize, raw: &str, expected: &str) {
+        let rules = ReductionRules::new(explode_depth, 10).unwrap();
+        assert_eq!(reduce(raw, &rules), expected);
+    }
+
+    #[test]
+    fn rejects_rules_that_break_reduction() {
+        assert_eq!(
+            ReductionRules::new(0, 10),
+            Err(RulesError::ExplodeDepthTooSmall(0))
+        );
+        assert_eq!(
+            ReductionRules::new(4, 1),
+            Err(RulesError::SplitThresholdTooSmall(1))
+        );
+        assert_eq!(
+            ReductionRules::new(4, 0).unwrap_err().to_string(),
+            "split threshold 0 is less than 2"
+        );
+        assert_eq!(ReductionRules::new(4, 10), Ok(ReductionRules::PUZZLE));
+        let rules = ReductionRules::new(1, 2).unwrap();
+        assert_eq!((rules.explode_depth(), rules.split_threshold()), (1, 2));
+    }
+
+    #[test_case(10, SplitRounding::LeftDown, "[7,1]", "[7,1]")]
+    #[test_case(5, SplitRounding::LeftDown, "[7,1]", "[[3,4],1]")]
+    #[test_case(5, SplitRounding::LeftUp, "[7,1]", "[[4,3],1]")]
+    #[test_case(2, SplitRounding::LeftUp, "[3,0]", "[[[1,1],1],0]")]
+    fn splits(split_threshold: usize, split_rounding: SplitRounding, raw: &str, expected: &str) {
+        let rules = ReductionRules::new(4, split_threshold)
+            .unwrap()
+            .with_split_rounding(split_rounding);
+        assert_eq!(reduce(raw, &rules), expected);
+    }
+
+    #[test_case((3, 2), "[[1,2],[[3,4],5]]", 143)]
+    #[test_case((1, 1), "[[1,2],[[3,4],5]]", 15)]
+    #[test_case((2, 3), "[9,1]", 21)]
+    fn magnitude_weights(magnitude_weights: (usize, usize), raw: &str, expected: usize) {
+        let rules = ReductionRules::default().with_magnitude_weights(magnitude_weights);
+        assert_eq!(Tree::from(raw).magnitude_with(0, &rules), expected);
+        assert_eq!(FlatTree::from(raw).magnitude_with(&rules), expected);
+    }
+
+    #[test]
+    fn representations_agree_on_sums() {
+        let rules = ReductionRules::new(3, 7)
+            .unwrap()
+            .with_split_rounding(SplitRounding::LeftUp)
+            .with_magnitude_weights((2, 5));
+        let lines = include_str!("input.txt")
+            .lines()
+            .take(20)
+            .collect::<Vec<_>>();
+
+        let mut tree = Tree::from(lines[0]);
+        let mut flat = FlatTree::from(lines[0]);
+        for line in &lines[1..] {
+            tree.add_with(&Tree::from(*line), &rules);
+            flat.add_with(&FlatTree::from(*line), &rules);
+            assert_eq!(tree.as_string(), flat.to_string());
+        }
+        assert_eq!(tree.magnitude_with(0, &rules), flat.magnitude_with(&rules));
+    }
+}
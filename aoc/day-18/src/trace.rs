@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use crate::{ReductionRules, Tree};
+
+/// Which child of a pair to go down to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// A single step of reducing a snailfish number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// The pair of regular numbers at `path` exploded.
+    Explode {
+        path: Vec<Direction>,
+        left: usize,
+        right: usize,
+    },
+    /// The regular number at `path` split into a pair.
+    Split { path: Vec<Direction>, value: usize },
+}
+
+/// An action, along with the snailfish number it left behind.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub action: Action,
+    pub tree: String,
+}
+
+/// Every step taken while reducing a snailfish number all the way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    /// The snailfish number before any reduction.
+    pub start: String,
+    pub steps: Vec<Step>,
+}
+
+impl Trace {
+    /// The snailfish number after all the reduction.
+    pub fn end(&self) -> &str {
+        self.steps.last().map_or(&self.start, |step| &step.tree)
+    }
+}
+
+/// Spell out a path as `L`s and `R`s from the root down.
+fn fmt_path(path: &[Direction]) -> String {
+    if path.is_empty() {
+        return "root".to_owned();
+    }
+    path.iter()
+        .map(|direction| match direction {
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+        })
+        .collect()
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Explode { path, left, right } => {
+                write!(f, "explode [{},{}] at {}", left, right, fmt_path(path))
+            }
+            Action::Split { path, value } => write!(f, "split {} at {}", value, fmt_path(path)),
+        }
+    }
+}
+
+impl Display for Trace {
+    /// Print the starting number and then one step per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "start: {}", self.start)?;
+        for step in &self.steps {
+            writeln!(f, "{}: {}", step.action, step.tree)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tree {
+    /// Reduce all the way with the given rules, recording every step along the way.
+    pub fn reduce_with_trace(&mut self, rules: &ReductionRules) -> Trace {
+        let start = self.as_string();
+        let mut steps = vec![];
+        while let Some(action) = self.reduce_step(rules) {
+            steps.push(Step {
+                action,
+                tree: self.as_string(),
+            });
+        }
+        Trace { start, steps }
+    }
+
+    /// Add `rhs` to us, and then reduce all the way with the given rules,
+    /// recording every step along the way.
+    pub fn add_with_trace(&mut self, rhs: &Self, rules: &ReductionRules) -> Trace {
+        self.join(rhs);
+        self.reduce_with_trace(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::*;
+
+    #[test]
+    fn puzzle_example() {
+        let mut tree = Tree::from("[[[[4,3],4],4],[7,[[8,4],9]]]");
+        let trace = tree.add_with_trace(&Tree::from("[1,1]"), &ReductionRules::PUZZLE);
+
+        assert_eq!(trace.start, "[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+        assert_eq!(
+            trace.steps,
+            vec![
+                Step {
+                    action: Action::Explode {
+                        path: vec![Left, Left, Left, Left],
+                        left: 4,
+                        right: 3
+                    },
+                    tree: "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]".to_owned(),
+                },
+                Step {
+                    action: Action::Explode {
+                        path: vec![Left, Right, Right, Left],
+                        left: 8,
+                        right: 4
+                    },
+                    tree: "[[[[0,7],4],[15,[0,13]]],[1,1]]".to_owned(),
+                },
+                Step {
+                    action: Action::Split {
+                        path: vec![Left, Right, Left],
+                        value: 15
+                    },
+                    tree: "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]".to_owned(),
+                },
+                Step {
+                    action: Action::Split {
+                        path: vec![Left, Right, Right, Right],
+                        value: 13
+                    },
+                    tree: "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]".to_owned(),
+                },
+                Step {
+                    action: Action::Explode {
+                        path: vec![Left, Right, Right, Right],
+                        left: 6,
+                        right: 7
+                    },
+                    tree: "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(trace.end(), tree.as_string());
+    }
+
+    #[test]
+    fn display() {
+        let mut tree = Tree::from("[[[[[9,8],1],2],3],4]");
+        let trace = tree.reduce_with_trace(&ReductionRules::PUZZLE);
+        assert_eq!(
+            trace.to_string(),
+            "start: [[[[[9,8],1],2],3],4]\nexplode [9,8] at LLLL: [[[[0,9],2],3],4]\n"
+        );
+
+        let mut tree = Tree::from("[11,1]");
+        let trace = tree.reduce_with_trace(&ReductionRules::PUZZLE);
+        assert_eq!(
+            trace.to_string(),
+            "start: [11,1]\nsplit 11 at L: [[5,6],1]\n"
+        );
+    }
+
+    #[test]
+    fn nothing_to_do() {
+        let mut tree = Tree::from("[1,2]");
+        let trace = tree.reduce_with_trace(&ReductionRules::PUZZLE);
+        assert!(trace.steps.is_empty());
+        assert_eq!(trace.end(), "[1,2]");
+    }
+}
@@ -1,40 +1,119 @@
-use crate::{parse::Parse, SnailFish};
-use std::{fmt::Display, str::FromStr};
-
-#[derive(Clone, Eq)]
+use crate::{Action, Direction, Pair, ParseError, ReductionRules, SnailFish, SnailfishNumber};
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+#[derive(Debug, Eq)]
 pub struct Tree {
     pub(crate) inner: Vec<Option<usize>>,
 }
 
+impl Clone for Tree {
+    fn clone(&self) -> Self {
+        Tree {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Reuse our buffer rather than allocating a new one.
+    fn clone_from(&mut self, source: &Self) {
+        self.inner.clone_from(&source.inner);
+    }
+}
+
 pub type Node = (usize, Option<usize>);
 
+/// The regular numbers in a tree from left to right, see [`Tree::regular_numbers`].
+///
+/// This walks the tree without a stack, since the index of a node
+/// is enough to find its parent and its siblings.
+#[derive(Clone, Debug)]
+pub struct RegularNumbers<'a> {
+    tree: &'a Tree,
+    /// The next node to look at, if there's anything left.
+    next: Option<usize>,
+}
+
+impl RegularNumbers<'_> {
+    /// The node to look at once everything below `index` has been looked at.
+    fn after(index: usize) -> Option<usize> {
+        let mut index = index;
+        // Go up until we're a left child, and then over to the right.
+        while index > 0 && index.is_multiple_of(2) {
+            index = (index - 1) / 2;
+        }
+        (index > 0).then_some(index + 1)
+    }
+}
+
+impl Iterator for RegularNumbers<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let index = self.next?;
+            match self.tree.inner.get(index) {
+                Some(Some(value)) => {
+                    self.next = Self::after(index);
+                    return Some((index, *value));
+                }
+                Some(None) => self.next = Some(2 * index + 1),
+                None => self.next = Self::after(index),
+            }
+        }
+    }
+}
+
 impl From<&str> for Tree {
     fn from(s: &str) -> Self {
-        Tree::from_str(s).unwrap()
+        Tree::from_str(s).unwrap_or_else(|e| panic!("invalid snailfish number {:?}: {}", s, e))
     }
 }
 
 impl FromStr for Tree {
-    type Err = String;
+    type Err = ParseError;
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        SnailFish::parse(s)
-            .map(|(_, t)| t.into())
-            .map_err(|e| e.to_string())
+        s.parse::<SnailFish>().map(Self::from)
     }
 }
 
-/// Call two trees equal if the regular numbers they store at the leaves
-/// are all equal.
+/// Call two trees equal if they have the same shape and the same regular numbers
+/// in the same places, regardless of how much room either of them has to spare.
 impl PartialEq for Tree {
     fn eq(&self, other: &Tree) -> bool {
-        self.inner
-            .iter()
-            .zip(other.inner.iter())
-            .all(|(&x, &y)| match (x, y) {
-                (Some(x), Some(y)) => x == y,
-                (None, None) => true,
-                _ => false,
-            })
+        self.regular_numbers().eq(other.regular_numbers())
+    }
+}
+
+impl Hash for Tree {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for regular_number in self.regular_numbers() {
+            regular_number.hash(state);
+        }
+    }
+}
+
+/// Order trees by their magnitude, breaking ties by their shape and regular numbers
+/// so that only equal trees compare equal.
+impl Ord for Tree {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let magnitude = |tree: &Tree| match tree.is_empty() {
+            true => 0,
+            false => tree.magnitude(0),
+        };
+        magnitude(self)
+            .cmp(&magnitude(other))
+            .then_with(|| self.regular_numbers().cmp(other.regular_numbers()))
+    }
+}
+
+impl PartialOrd for Tree {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -51,6 +130,9 @@ impl Tree {
     pub fn len(&self) -> usize {
         self.inner.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
 
     /// Set the left child of the `parent` node at the given index to `node`.
     pub fn set_left(&mut self, parent: usize, node: usize) {
@@ -242,6 +324,12 @@ impl Tree {
     /// with the numbers being half of the original number rounded down and up,
     /// respectively.
     pub fn split(&mut self, index: usize) {
+        self.split_with(index, &ReductionRules::PUZZLE)
+    }
+
+    /// Given the index of a regular node, split it into a pair of regular nodes
+    /// with the numbers being half of the original number, rounded as the rules say.
+    pub fn split_with(&mut self, index: usize, rules: &ReductionRules) {
         let (node_index, node) = self.at(index);
         if node.is_none() {
             panic!("Tried to split a irregular node at : {}", node_index);
@@ -252,75 +340,58 @@ impl Tree {
 
         // Since its a regular node, its not gonna have any children.
         // and we can just set the left and right to the new values.
-        self.set_left(index, node.unwrap() / 2);
-        self.set_right(index, (node.unwrap() + 1) / 2);
-    }
-
-    /// Iterate over the tree in-order and build a vector representing
-    /// the bracket string representation of the tree.
-    pub fn inorder_traverse(&self, index: usize, result: &mut Vec<String>) {
-        if self.has_left(index) && self.has_right(index) {
-            if let Some(last) = result.last() {
-                if last == "]" {
-                    result.push(",".to_owned());
-                }
-            }
-            result.push("[".to_owned());
-        }
-
-        if self.has_left(index) {
-            self.inorder_traverse(self.left(index).0, result);
-            if self.at(self.left(index).0).1.is_some() {
-                if let Some(last) = result.last() {
-                    if last == "]" {
-                        result.push(",".to_owned());
-                    }
-                }
-                result.push(self.at(self.left(index).0).1.unwrap().to_string());
-                result.push(",".to_owned());
-            }
-        }
-
-        if self.has_right(index) {
-            self.inorder_traverse(self.right(index).0, result);
-            if self.at(self.right(index).0).1.is_some() {
-                // If just closed a pair, add a comma.
-                if let Some(last) = result.last() {
-                    if last == "]" {
-                        result.push(",".to_owned());
-                    }
-                }
-                result.push(self.at(self.right(index).0).1.unwrap().to_string());
-            }
-        }
-
-        if self.has_left(index) && self.has_right(index) {
-            // Empty parenthesis can be removed.
-            if result.last().unwrap() == "[" {
-                result.pop();
-            } else {
-                result.push("]".to_owned());
-            }
-        }
+        let (left, right) = rules.halves(node.unwrap());
+        self.set_left(index, left);
+        self.set_right(index, right);
     }
 
     /// Build the bracket representation of the tree
     /// to help with debugging.
     pub fn as_string(&self) -> String {
-        let mut result = vec![];
-        self.inorder_traverse(0, &mut result);
-        result.join("")
+        self.to_string()
     }
 
     pub fn find_index_of_child_whose_parent_to_explode(&self) -> Option<usize> {
-        // We'll search from the left by default so the first
-        // node we find at at the 5th depth (i.e. inside 4 brackets) is the node whose
-        // parent we want to explode.
-        self.nodes_at_depth(5)
-            .filter(|(_, node_value)| node_value.is_some())
-            .map(|(node_index, _)| node_index)
-            .take(1)
-            .next()
+        self.find_index_of_child_whose_parent_to_explode_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Find the left child of the left-most pair of regular numbers
+    /// nested inside more than `rules.explode_depth()` pairs.
+    pub fn find_index_of_child_whose_parent_to_explode_with(
+        &self,
+        rules: &ReductionRules,
+    ) -> Option<usize> {
+        // A pair of regular numbers shows up as a left child immediately
+        // followed by its right sibling amongst the regular numbers in order.
+        let mut regular_numbers = vec![];
+        self.regular_numbers_in_order(0, &mut regular_numbers);
+        regular_numbers
+            .windows(2)
+            .find(|w| w[0] % 2 == 1 && w[1] == w[0] + 1 && depth_of(w[0]) > rules.explode_depth())
+            .map(|w| w[0])
+    }
+
+    /// The regular numbers in the tree from left to right, as `(index, value)`.
+    ///
+    /// This pins down the whole tree, since the index of a node says where it is.
+    pub fn regular_numbers(&self) -> RegularNumbers<'_> {
+        RegularNumbers {
+            tree: self,
+            next: Some(0),
+        }
+    }
+
+    /// Collect the indices of the regular numbers in the sub-tree rooted at `root`, from left to right.
+    pub fn regular_numbers_in_order(&self, root: usize, result: &mut Vec<usize>) {
+        if root >= self.len() {
+            return;
+        }
+        if self.at(root).1.is_some() {
+            result.push(root);
+            return;
+        }
+        self.regular_numbers_in_order(2 * root + 1, result);
+        self.regular_numbers_in_order(2 * root + 2, result);
     }
 
     /// Find the index of the left-most regular number in a sub-tree rooted at `root`
@@ -412,25 +483,51 @@ impl Tree {
     /// If we can find an pair 4 levels deep, explode it. Otherwise if we can find a number we can split,
     /// split it. Otherwise, we're done. If we didn't do either, return False, otherwise return True.
     pub fn reduce(&mut self) -> bool {
-        if let Some(index) = self.find_index_of_child_whose_parent_to_explode() {
-            self.explode_parent(self.parent(index).0);
-            true
+        self.reduce_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Reduce once, with the given rules in place of the puzzle's.
+    pub fn reduce_with(&mut self, rules: &ReductionRules) -> bool {
+        self.reduce_step(rules).is_some()
+    }
+
+    /// Reduce once with the given rules, returning what we did, if anything.
+    pub fn reduce_step(&mut self, rules: &ReductionRules) -> Option<Action> {
+        if let Some(index) = self.find_index_of_child_whose_parent_to_explode_with(rules) {
+            let parent = self.parent(index).0;
+            let action = Action::Explode {
+                path: path_to(parent),
+                left: self.left(parent).1.unwrap(),
+                right: self.right(parent).1.unwrap(),
+            };
+            self.explode_parent(parent);
+            Some(action)
         } else {
             let mut first_large_number = None;
-            self.find_leftmost_regular_number_ge(0, 10, &mut first_large_number);
-            if let Some(index) = first_large_number {
-                self.split(index);
-                true
-            } else {
-                false
-            }
+            self.find_leftmost_regular_number_ge(
+                0,
+                rules.split_threshold(),
+                &mut first_large_number,
+            );
+            let index = first_large_number?;
+            let action = Action::Split {
+                path: path_to(index),
+                value: self.at(index).1.unwrap(),
+            };
+            self.split_with(index, rules);
+            Some(action)
         }
     }
 
     /// Repeatedly reduce until we can't any more.
     pub fn reduce_all_the_way(&mut self) {
+        self.reduce_all_the_way_with(&ReductionRules::PUZZLE)
+    }
+
+    /// Repeatedly reduce with the given rules until we can't any more.
+    pub fn reduce_all_the_way_with(&mut self, rules: &ReductionRules) {
         loop {
-            if !self.reduce() {
+            if !self.reduce_with(rules) {
                 break;
             }
         }
@@ -440,6 +537,13 @@ impl Tree {
     /// i.e. for pairs it is the 3 * magnitude(left) + 2 * magnitude(right)
     /// and for literals it is the literal value.
     pub fn magnitude(&self, root: usize) -> usize {
+        self.magnitude_with(root, &ReductionRules::PUZZLE)
+    }
+
+    /// Get the magnitude of a snailfish number, weighing the left
+    /// and right magnitudes of pairs as the rules say.
+    pub fn magnitude_with(&self, root: usize, rules: &ReductionRules) -> usize {
+        let (left_weight, right_weight) = rules.magnitude_weights();
         let mut result: usize = 0;
 
         let (_, current_value) = self.at(root);
@@ -450,12 +554,12 @@ impl Tree {
 
         if self.has_left(root) {
             let (index, _) = self.left(root);
-            result += 3 * self.magnitude(index);
+            result += left_weight * self.magnitude_with(index, rules);
         }
 
         if self.has_right(root) {
             let (index, _) = self.right(root);
-            result += 2 * self.magnitude(index);
+            result += right_weight * self.magnitude_with(index, rules);
         }
 
         result
@@ -464,24 +568,114 @@ impl Tree {
     /// Given another tree, add that to us, mutating
     /// ourselves in the process. Then reduce ourselves all the way.
     pub fn add(&mut self, rhs: &Self) {
-        // It's kinda expensive (i.e. O(n) time + roughly O(2 ** n) space) anyways
-        // because of the linear representation of the binary tree
-        // so might as well bring in the existing parser setup.
+        self.add_with(rhs, &ReductionRules::PUZZLE)
+    }
+
+    /// Add another tree to us, and then reduce ourselves all the way with the given rules.
+    pub fn add_with(&mut self, rhs: &Self, rules: &ReductionRules) {
+        self.join(rhs);
+        self.reduce_all_the_way_with(rules);
+    }
+
+    /// Make us the pair of us and `rhs`, without reducing.
+    pub fn join(&mut self, rhs: &Self) {
+        // Moving a sub-tree one level down keeps every node at the same offset within
+        // its depth, so the node at `index` and depth `d` moves to `index + 2^d` when it
+        // goes under the left of the new root and to `index + 2^(d + 1)` under the right.
+        let last = |len: usize, shift: usize| {
+            len.checked_sub(1)
+                .map_or(0, |index| index + (shift << depth_of(index)) + 1)
+        };
+        // Leave out the empty slots at the back, or else they'd pile up with every addition.
+        let used = |tree: &Tree| {
+            tree.inner
+                .iter()
+                .rposition(Option::is_some)
+                .map_or(0, |i| i + 1)
+        };
+        let (ours, theirs) = (used(self), used(rhs));
+        self.inner.truncate(ours);
+        self.inner.resize(last(ours, 1).max(last(theirs, 2)), None);
+
+        // Every node moves further back, so going from the back
+        // means nothing gets overwritten before it's moved.
+        for index in (0..ours).rev() {
+            if let Some(value) = self.inner[index].take() {
+                self.inner[index + (1 << depth_of(index))] = Some(value);
+            }
+        }
+        for (index, &node) in rhs.inner[..theirs].iter().enumerate() {
+            if node.is_some() {
+                self.inner[index + (2 << depth_of(index))] = node;
+            }
+        }
 
         // I'd guess a better way to implement this Tree would be a HashMap<usize, usize>
         // where we can treat absence of keys as the absence of nodes and nodes are encoded
         // the same way as this linear representation of binary tree (i.e. left = 2 * parent + 1, right = 2 * parent + 2).
-        let as_str = format!("[{},{}]", self.as_string(), rhs.as_string());
-        let mut initial: Tree = Tree::from(as_str.as_str());
+        // That'd save us the roughly O(2 ** n) space all these empty slots take up.
+    }
+}
+
+impl SnailfishNumber for Tree {
+    fn add_with(&mut self, rhs: &Self, rules: &ReductionRules) {
+        Tree::add_with(self, rhs, rules)
+    }
 
-        initial.reduce_all_the_way();
-        self.inner = initial.inner;
+    fn reduce_with(&mut self, rules: &ReductionRules) -> bool {
+        Tree::reduce_with(self, rules)
+    }
+
+    fn magnitude_with(&self, rules: &ReductionRules) -> usize {
+        Tree::magnitude_with(self, 0, rules)
     }
 }
 
+/// The way down from the root to the node at the given index.
+fn path_to(mut index: usize) -> Vec<Direction> {
+    let mut path = vec![];
+    while index > 0 {
+        path.push(if index % 2 == 1 {
+            Direction::Left
+        } else {
+            Direction::Right
+        });
+        index = (index - 1) / 2;
+    }
+    path.reverse();
+    path
+}
+
+/// The number of pairs the node at the given index is nested inside.
+pub(crate) fn depth_of(index: usize) -> usize {
+    (index + 1).ilog2() as usize
+}
+
 impl Display for Tree {
+    /// Write the bracket representation of the tree, or nothing at all if it's empty.
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        write!(f, "{}", self.as_string())
+        if self.is_empty() {
+            return Ok(());
+        }
+        write!(f, "{}", SnailFish::from(self))
+    }
+}
+
+impl From<&Tree> for SnailFish {
+    /// Rebuild the snailfish representation of the tree.
+    ///
+    /// Panics if the tree is empty, since there's no snailfish number to rebuild.
+    fn from(tree: &Tree) -> Self {
+        fn node(tree: &Tree, index: usize) -> SnailFish {
+            match tree.at(index) {
+                (_, Some(value)) => SnailFish::Literal(value),
+                (index, None) => SnailFish::Pair(Box::new(Pair {
+                    left: node(tree, 2 * index + 1),
+                    right: node(tree, 2 * index + 2),
+                })),
+            }
+        }
+        node(tree, 0)
     }
 }
 
@@ -509,6 +703,7 @@ impl From<SnailFish> for Tree {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Parse;
     use test_case::test_case;
 
     #[test]
@@ -658,4 +853,155 @@ mod tests {
         tree.reduce();
         assert_eq!(tree.as_string(), expected);
     }
+
+    #[test]
+    fn equality_ignores_padding() {
+        let tree = Tree::from("[[1,2],3]");
+        let mut padded = tree.clone();
+        padded.inner.resize(64, None);
+        assert_eq!(tree, padded);
+
+        // Exploding leaves the emptied slots behind.
+        let mut exploded = Tree::from("[[[[[9,8],1],2],3],4]");
+        exploded.reduce();
+        assert!(exploded.len() > Tree::from("[[[[0,9],2],3],4]").len());
+        assert_eq!(exploded, Tree::from("[[[[0,9],2],3],4]"));
+    }
+
+    #[test_case("[1,2]", "[2,1]")]
+    #[test_case("[[1,2],3]", "[1,[2,3]]")]
+    #[test_case("[[1,2],3]", "[[1,2],[3,0]]")]
+    fn inequality(a: &str, b: &str) {
+        let (a, b) = (Tree::from(a), Tree::from(b));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn prefix_is_not_equal() {
+        // The old comparison zipped the slots and so stopped at the shorter tree.
+        let long = Tree::from("[[1,2],3]");
+        let mut short = long.clone();
+        short.inner.truncate(3);
+        assert_ne!(long, short);
+        assert_ne!(short, long);
+    }
+
+    #[test_case("7", &[(0, 7)])]
+    #[test_case("[1,2]", &[(1, 1), (2, 2)])]
+    #[test_case("[[1,2],3]", &[(3, 1), (4, 2), (2, 3)])]
+    #[test_case("[1,[2,[3,4]]]", &[(1, 1), (5, 2), (13, 3), (14, 4)])]
+    fn regular_numbers(raw: &str, expected: &[(usize, usize)]) {
+        let tree = Tree::from(raw);
+        assert_eq!(tree.regular_numbers().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn regular_numbers_skip_leftovers() {
+        let mut exploded = Tree::from("[[[[[9,8],1],2],3],4]");
+        exploded.reduce();
+        let mut short = Tree::from("[[1,2],3]");
+        short.inner.truncate(3);
+        for tree in [exploded, short, Tree::new()] {
+            let mut indices = vec![];
+            tree.regular_numbers_in_order(0, &mut indices);
+            let found = tree.regular_numbers().map(|(index, _)| index);
+            assert_eq!(found.collect::<Vec<_>>(), indices);
+        }
+    }
+
+    #[test]
+    fn dedup_and_map_keys() {
+        use std::collections::{HashMap, HashSet};
+
+        let mut exploded = Tree::from("[[[[[9,8],1],2],3],4]");
+        exploded.reduce();
+        let trees = [
+            Tree::from("[[[[0,9],2],3],4]"),
+            exploded,
+            Tree::from("[1,2]"),
+        ];
+        let unique = trees.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 2);
+
+        let mut counts = HashMap::new();
+        for tree in trees {
+            *counts.entry(tree).or_insert(0) += 1;
+        }
+        assert_eq!(counts[&Tree::from("[[[[0,9],2],3],4]")], 2);
+    }
+
+    #[test]
+    fn ordered_by_magnitude() {
+        let mut trees = [
+            Tree::from("[[1,2],[[3,4],5]]"),
+            Tree::from("[9,1]"),
+            Tree::from("[1,9]"),
+            Tree::from("[[9,1],[1,9]]"),
+        ];
+        trees.sort();
+        let sorted = trees.iter().map(|t| t.as_string()).collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            ["[1,9]", "[9,1]", "[[9,1],[1,9]]", "[[1,2],[[3,4],5]]"]
+        );
+
+        // Same magnitude, different trees.
+        let (a, b) = (Tree::from("[0,3]"), Tree::from("[2,0]"));
+        assert_eq!(a.magnitude(0), b.magnitude(0));
+        assert_ne!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(a.cmp(&a.clone()), Ordering::Equal);
+        assert_eq!(Tree::new().cmp(&Tree::new()), Ordering::Equal);
+    }
+
+    #[test_case("7")]
+    #[test_case("[1,2]")]
+    #[test_case("[[[[[9,8],1],2],3],4]")]
+    #[test_case("[7,[6,[5,[4,[3,2]]]]]")]
+    #[test_case("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]")]
+    fn round_trip(raw: &str) {
+        let snailfish = raw.parse::<SnailFish>().unwrap();
+        let tree = Tree::from(raw);
+        assert_eq!(SnailFish::from(&tree), snailfish);
+        assert_eq!(tree.to_string(), raw);
+        assert_eq!(snailfish.to_string(), raw);
+    }
+
+    #[test]
+    fn round_trip_while_reducing() {
+        // Reducing leaves cleared slots behind, which shouldn't show up in the output.
+        let numbers = include_str!("input.txt").lines().collect::<Vec<_>>();
+        let mut sum = Tree::from(numbers[0]);
+        for number in &numbers[1..] {
+            assert_eq!(
+                Tree::from(number.parse::<SnailFish>().unwrap()).to_string(),
+                *number
+            );
+            sum.join(&Tree::from(*number));
+            loop {
+                let rebuilt = Tree::from(SnailFish::from(&sum));
+                assert_eq!(rebuilt, sum);
+                assert_eq!(rebuilt.to_string(), sum.to_string());
+                assert_eq!(sum.to_string().parse::<Tree>().unwrap(), sum);
+                if !sum.reduce() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_tree_prints_nothing() {
+        assert_eq!(Tree::new().to_string(), "");
+    }
+
+    #[test]
+    fn joining_leaves_out_empty_slots() {
+        let mut sum = Tree::from("[[[[[9,8],1],2],3],4]");
+        sum.reduce();
+        for line in include_str!("input.txt").lines() {
+            sum.add(&Tree::from(line));
+            // Nothing gets nested deeper than 5 pairs while reducing, which fits in 63 slots.
+            assert!(sum.len() <= 63, "{} slots", sum.len());
+        }
+    }
 }
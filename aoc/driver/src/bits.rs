@@ -0,0 +1,86 @@
+use clap::Subcommand;
+use day_16::TransmissionReader;
+
+#[derive(Debug, Subcommand)]
+pub enum BitsCommand {
+    /// Evaluate every transmission in a hex string.
+    Eval {
+        #[arg(long, help = "Print every operator application made during evaluation.")]
+        trace: bool,
+        #[arg(long, requires = "trace", help = "Print the trace as JSON instead of an indented log.")]
+        json: bool,
+        #[arg(help = "The hex encoded transmission.")]
+        hex: String,
+    },
+    /// Dump every transmission in a hex string as JSON.
+    Decode {
+        #[arg(help = "The hex encoded transmission.")]
+        hex: String,
+    },
+}
+
+pub fn run(command: BitsCommand) {
+    match command {
+        BitsCommand::Eval { trace, json, hex } => eval(&hex, trace, json),
+        BitsCommand::Decode { hex } => decode(&hex),
+    }
+}
+
+fn decode(hex: &str) {
+    let transmissions = TransmissionReader::new(hex.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    // Dump every transmission on its own, so a packet that can't be evaluated is reported where it is.
+    let dumps = transmissions
+        .iter()
+        .map(|transmission| {
+            serde_json::to_value(transmission).unwrap_or_else(|e| {
+                eprintln!(
+                    "line {}, bit {}: {}",
+                    transmission.line, transmission.offset, e
+                );
+                std::process::exit(1);
+            })
+        })
+        .collect::<Vec<_>>();
+    println!("{}", serde_json::to_string_pretty(&dumps).unwrap());
+}
+
+fn eval(hex: &str, trace: bool, json: bool) {
+    for result in TransmissionReader::new(hex.as_bytes()) {
+        let transmission = match result {
+            Ok(transmission) => transmission,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        let fail = |e| -> ! {
+            eprintln!(
+                "line {}, bit {}: {}",
+                transmission.line, transmission.offset, e
+            );
+            std::process::exit(1);
+        };
+
+        if !trace {
+            let value = transmission.packet.value().unwrap_or_else(|e| fail(e));
+            println!("{}", value);
+            continue;
+        }
+
+        let trace = transmission
+            .packet
+            .evaluate_with_trace()
+            .unwrap_or_else(|e| fail(e));
+        if json {
+            println!("{}", serde_json::to_string_pretty(&trace).unwrap());
+        } else {
+            println!("{}", trace);
+        }
+    }
+}
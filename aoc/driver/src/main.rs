@@ -1,19 +1,40 @@
+mod bits;
+mod probe;
+mod snailfish;
+
 use aoc_core::Solution;
-use clap::Parser;
-use clap::arg;
-use clap::command;
-use day_16;
-use day_17;
-use day_18;
+use clap::{Parser, Subcommand};
 
 
 #[derive(Debug, Parser)]
 #[command(author = "Aalekh Patel", version = "0.1.0", about = "AOC driver.", long_about = "Run the solutions implemented in this workspace.")]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
-    #[arg(short, long, help="The day to compute solutions for.")]
-    day: usize,
+    #[arg(short, long, required = true, help="The day to compute solutions for.")]
+    day: Option<usize>,
     #[arg(short, long, help="The part to solve. If provided, will solve part 2.", default_value_t = false)]
-    second: bool
+    second: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Inspect BITS transmissions (Day 16).
+    Bits {
+        #[command(subcommand)]
+        command: bits::BitsCommand,
+    },
+    /// Explore the probe launches (Day 17).
+    Probe {
+        #[command(subcommand)]
+        command: probe::ProbeCommand,
+    },
+    /// Inspect snailfish arithmetic (Day 18).
+    Snailfish {
+        #[command(subcommand)]
+        command: snailfish::SnailfishCommand,
+    },
 }
 
 fn solve<S: Solution>(solver: S, second: bool) -> String {
@@ -27,17 +48,28 @@ fn solve<S: Solution>(solver: S, second: bool) -> String {
 fn main() {
     let args = Args::parse();
 
+    if let Some(command) = args.command {
+        match command {
+            Command::Bits { command } => bits::run(command),
+            Command::Probe { command } => probe::run(command),
+            Command::Snailfish { command } => snailfish::run(command),
+        }
+        return;
+    }
+
+    let day = args.day.unwrap();
+
     // Tried creating a Hashmap<usize, Box<dyn Solver>>
     // but the compiler is not happy and complains about
     // it unable to make the trait into an object because
     // it requires that Self: Sized. (I'm a bit lost here.)
 
-    let answer: String = match args.day {
+    let answer: String = match day {
         16 => solve(day_16::Solver::default(), args.second),
         17 => solve(day_17::Solver::default(), args.second),
         18 => solve(day_18::Solver::default(), args.second),
         _ => unimplemented!("")
     };
 
-    println!("Day: {}, Part: {}, Answer: {}", args.day, {if args.second {"2"} else {"1"}}, answer);
+    println!("Day: {}, Part: {}, Answer: {}", day, {if args.second {"2"} else {"1"}}, answer);
 }
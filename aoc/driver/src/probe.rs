@@ -0,0 +1,100 @@
+use clap::{Subcommand, ValueEnum};
+use std::ops::RangeInclusive;
+
+use day_17::{hitting_shots, parse_target_area, GameState, Plot, Shot};
+
+#[derive(Debug, Subcommand)]
+pub enum ProbeCommand {
+    /// List every shot that hits the target area as CSV.
+    Shots {
+        #[arg(long, value_enum, default_value_t = SortKey::Velocity, help = "The column to sort the shots by.")]
+        sort_by: SortKey,
+        #[arg(help = "The target area, like \"target area: x=20..30, y=-10..-5\". Defaults to the puzzle input.")]
+        target: Option<String>,
+    },
+    /// Plot some shots at the target area as SVG.
+    Plot {
+        #[arg(long = "shot", value_name = "VX,VY", value_parser = parse_velocity, allow_hyphen_values = true, help = "A velocity to launch the probe with. Can be repeated. Defaults to the shot that goes the highest and the one launched just above it.")]
+        shots: Vec<(i32, i32)>,
+        #[arg(help = "The target area, like \"target area: x=20..30, y=-10..-5\". Defaults to the puzzle input.")]
+        target: Option<String>,
+    },
+}
+
+fn parse_velocity(s: &str) -> Result<(i32, i32), String> {
+    let (vx, vy) = s.split_once(',').ok_or_else(|| format!("expected VX,VY but got {:?}", s))?;
+    let parse = |v: &str| v.trim().parse::<i32>().map_err(|e| format!("{:?}: {}", v, e));
+    Ok((parse(vx)?, parse(vy)?))
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SortKey {
+    Velocity,
+    Step,
+    Apex,
+}
+
+pub fn run(command: ProbeCommand) {
+    match command {
+        ProbeCommand::Shots { sort_by, target } => shots(target.as_deref().unwrap_or(day_17::INPUT), sort_by),
+        ProbeCommand::Plot { shots, target } => plot(target.as_deref().unwrap_or(day_17::INPUT), shots),
+    }
+}
+
+fn target_area(input: &str) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+    match parse_target_area::<i32>(input) {
+        Ok((_, target_area)) => target_area,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Find every shot that hits the target area, or exit if there are infinitely many.
+fn all_hitting_shots(x_range: &RangeInclusive<i32>, y_range: &RangeInclusive<i32>) -> Vec<Shot> {
+    hitting_shots(x_range, y_range).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn shots(input: &str, sort_by: SortKey) {
+    let (x_range, y_range) = target_area(input);
+
+    let mut shots = all_hitting_shots(&x_range, &y_range);
+    match sort_by {
+        SortKey::Velocity => {}
+        SortKey::Step => shots.sort_by_key(|shot| shot.step),
+        SortKey::Apex => shots.sort_by_key(|shot| std::cmp::Reverse(shot.apex)),
+    }
+    match day_17::write_csv(&shots, std::io::stdout().lock()) {
+        // Whatever we're piped into has seen enough, like `head`.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("could not write the shots: {}", e);
+            std::process::exit(1);
+        }
+        Ok(()) => {}
+    }
+}
+
+fn plot(input: &str, mut velocities: Vec<(i32, i32)>) {
+    let (x_range, y_range) = target_area(input);
+
+    if velocities.is_empty() {
+        let best = all_hitting_shots(&x_range, &y_range)
+            .into_iter()
+            .max_by_key(|shot| shot.apex);
+        if let Some(best) = best {
+            let (vx, vy) = best.velocity;
+            velocities = vec![(vx, vy), (vx, vy + 1)];
+        }
+    }
+
+    let mut plot = Plot::new(x_range.clone(), y_range.clone());
+    for (vx, vy) in velocities {
+        plot.add(GameState::new(x_range.clone(), y_range.clone(), vx, vy, 0, 0));
+    }
+    print!("{}", plot.to_svg());
+}
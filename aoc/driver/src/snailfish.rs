@@ -0,0 +1,51 @@
+use clap::Subcommand;
+use day_18::{ReductionRules, Tree};
+
+#[derive(Debug, Subcommand)]
+pub enum SnailfishCommand {
+    /// Add two snailfish numbers and print every step of the reduction.
+    Trace {
+        #[arg(help = "The snailfish number on the left, like \"[[1,2],3]\".")]
+        left: String,
+        #[arg(help = "The snailfish number on the right.")]
+        right: String,
+    },
+    /// Draw a snailfish number as a Graphviz DOT graph, e.g. for `dot -Tsvg`.
+    Dot {
+        #[arg(long, default_value_t = 0, help = "How many times to explode or split the number before drawing it.")]
+        steps: usize,
+        #[arg(help = "The snailfish number to draw, like \"[[1,2],3]\".")]
+        number: String,
+    },
+}
+
+pub fn run(command: SnailfishCommand) {
+    match command {
+        SnailfishCommand::Trace { left, right } => trace(&left, &right),
+        SnailfishCommand::Dot { steps, number } => dot(&number, steps),
+    }
+}
+
+fn parse(s: &str) -> Tree {
+    s.parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+fn trace(left: &str, right: &str) {
+    let mut tree = parse(left);
+    let trace = tree.add_with_trace(&parse(right), &ReductionRules::PUZZLE);
+    print!("{}", trace);
+    println!("magnitude: {}", tree.magnitude(0));
+}
+
+fn dot(number: &str, steps: usize) {
+    let mut tree = parse(number);
+    for _ in 0..steps {
+        if !tree.reduce() {
+            break;
+        }
+    }
+    print!("{}", tree.to_dot());
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::game::{Outcome, Win};
+use crate::{Bingo, WinCondition, WinningLine};
+
+/// The marks on a board, one bit per cell, counting row by row from the top left.
+type Marks = u128;
+
+/// The largest board the engine can keep track of.
+pub const MAX_CELLS: usize = Marks::BITS as usize;
+
+/// The lines that win boards of one shape and set of win conditions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Lines {
+    /// Every winning line along with the cells it needs, in the order `Bingo` tries them.
+    masks: Vec<(Marks, WinningLine)>,
+    /// For every cell, the indices into `masks` of the lines going through it.
+    through: Vec<Vec<usize>>,
+}
+
+/// The marks of the given cells on a board with `cols` columns.
+fn mask(cols: usize, cells: impl IntoIterator<Item = (usize, usize)>) -> Marks {
+    cells.into_iter().fold(0, |mask, (row_idx, col_idx)| {
+        mask | 1 << (row_idx * cols + col_idx)
+    })
+}
+
+impl Lines {
+    fn new(rows: usize, cols: usize, win_conditions: &[WinCondition]) -> Self {
+        let mut masks = Vec::new();
+        if rows > 0 && cols > 0 {
+            for condition in win_conditions {
+                match condition {
+                    WinCondition::Row => masks.extend((0..rows).map(|row_idx| {
+                        let cells = (0..cols).map(|col_idx| (row_idx, col_idx));
+                        (mask(cols, cells), WinningLine::Row(row_idx))
+                    })),
+                    WinCondition::Column => masks.extend((0..cols).map(|col_idx| {
+                        let cells = (0..rows).map(|row_idx| (row_idx, col_idx));
+                        (mask(cols, cells), WinningLine::Column(col_idx))
+                    })),
+                    WinCondition::Diagonal if rows != cols => {}
+                    WinCondition::Diagonal => {
+                        let diagonal = (0..rows).map(|idx| (idx, idx));
+                        let anti = (0..rows).map(|idx| (idx, cols - 1 - idx));
+                        masks.push((mask(cols, diagonal), WinningLine::Diagonal));
+                        masks.push((mask(cols, anti), WinningLine::AntiDiagonal));
+                    }
+                    WinCondition::FullCard => {
+                        let all = (0..rows * cols).fold(0, |mask, cell_idx| mask | 1 << cell_idx);
+                        masks.push((all, WinningLine::FullCard));
+                    }
+                    WinCondition::FourCorners => {
+                        let corners = [(0, 0), (0, cols - 1), (rows - 1, 0), (rows - 1, cols - 1)];
+                        masks.push((mask(cols, corners), WinningLine::FourCorners));
+                    }
+                }
+            }
+        }
+
+        let through = (0..rows * cols)
+            .map(|cell_idx| {
+                (0..masks.len())
+                    .filter(|&mask_idx| masks[mask_idx].0 & (1 << cell_idx) != 0)
+                    .collect()
+            })
+            .collect();
+        Self { masks, through }
+    }
+}
+
+/// A board as the engine sees it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Board {
+    marks: Marks,
+    /// The sum of the numbers that haven't been marked yet.
+    unplayed_sum: usize,
+    /// The index of the board's winning lines among the engine's.
+    lines: usize,
+    won: bool,
+}
+
+/// A faster way of playing a [`BingoGame`](crate::game::BingoGame), for lots of boards and calls.
+///
+/// Rather than every board looking up every call, the engine looks up once which cells
+/// of which boards hold each number. Marks are kept as bitmasks, and a board has won
+/// when its marks cover one of its winning lines, which are worked out once
+/// for every shape of board and set of win conditions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitmaskGame {
+    boards: Vec<Board>,
+    lines: Vec<Lines>,
+    /// For every number, the boards it's on and the cell it's in, ordered by board.
+    cells: HashMap<usize, Vec<(usize, usize)>>,
+    calls: Vec<usize>,
+}
+
+impl BitmaskGame {
+    /// Set up a game with the given boards, or `None` if a board has more than [`MAX_CELLS`] cells.
+    pub fn new(boards: &[Bingo], calls: Vec<usize>) -> Option<Self> {
+        let mut line_sets: HashMap<(usize, usize, &[WinCondition]), usize> = HashMap::new();
+        let mut lines = Vec::new();
+        let mut cells: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        let mut engine_boards = Vec::with_capacity(boards.len());
+
+        for (board_idx, bingo) in boards.iter().enumerate() {
+            let (rows, cols) = bingo.dimensions();
+            if rows * cols > MAX_CELLS {
+                return None;
+            }
+            let key = (rows, cols, bingo.win_conditions());
+            let lines_idx = *line_sets.entry(key).or_insert_with(|| {
+                lines.push(Lines::new(rows, cols, bingo.win_conditions()));
+                lines.len() - 1
+            });
+
+            // Like `Bingo`, only the last cell holding a number gets marked when it's called.
+            let mut value_cell_map: HashMap<usize, usize> = HashMap::new();
+            for (cell_idx, &value) in bingo.entries.iter().flatten().enumerate() {
+                value_cell_map.insert(value, cell_idx);
+            }
+            for (value, cell_idx) in value_cell_map {
+                cells.entry(value).or_default().push((board_idx, cell_idx));
+            }
+
+            engine_boards.push(Board {
+                marks: 0,
+                unplayed_sum: bingo.entries.iter().flatten().sum(),
+                lines: lines_idx,
+                won: false,
+            });
+        }
+
+        Some(Self {
+            boards: engine_boards,
+            lines,
+            cells,
+            calls,
+        })
+    }
+
+    /// Call the numbers in order until every board has won or we run out of numbers,
+    /// with the same outcome as [`BingoGame::play`](crate::game::BingoGame::play).
+    pub fn play(mut self) -> Outcome {
+        let mut wins = Vec::new();
+        let mut playing = self.boards.len();
+
+        for (call_idx, &number) in self.calls.iter().enumerate() {
+            if playing == 0 {
+                break;
+            }
+            let Some(cells) = self.cells.get(&number) else {
+                continue;
+            };
+            for &(board_idx, cell_idx) in cells {
+                let board = &mut self.boards[board_idx];
+                let bit = 1 << cell_idx;
+                if board.won || board.marks & bit != 0 {
+                    continue;
+                }
+                board.marks |= bit;
+                board.unplayed_sum -= number;
+
+                // The board hadn't won before, so any line it wins with goes through this cell.
+                let lines = &self.lines[board.lines];
+                let line = lines.through[cell_idx]
+                    .iter()
+                    .map(|&mask_idx| lines.masks[mask_idx])
+                    .find(|&(mask, _)| board.marks & mask == mask);
+                if let Some((_, line)) = line {
+                    board.won = true;
+                    playing -= 1;
+                    wins.push(Win {
+                        board: board_idx,
+                        call_idx,
+                        number,
+                        line,
+                        score: number * board.unplayed_sum,
+                    });
+                }
+            }
+        }
+
+        Outcome { wins }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BingoGame;
+
+    /// A small deterministic generator, so that the random boards are the same every run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) % bound as u64) as usize
+        }
+    }
+
+    fn random_board(rng: &mut Lcg, rows: usize, cols: usize, max_value: usize) -> Bingo {
+        let entries = (0..rows)
+            .map(|_| (0..cols).map(|_| rng.next(max_value)).collect())
+            .collect();
+        Bingo::from_rows(entries).unwrap()
+    }
+
+    /// Check that both engines agree on the given boards and calls.
+    fn assert_same_outcome(boards: Vec<Bingo>, calls: Vec<usize>) {
+        let fast = BitmaskGame::new(&boards, calls.clone()).unwrap().play();
+        let slow = BingoGame::new(boards, calls).play();
+        assert_eq!(fast, slow);
+    }
+
+    #[test]
+    fn test_puzzle_input() {
+        let input = include_str!("../../../data/04.in");
+        let (calls, boards) = input.split_once('\n').unwrap();
+        let calls = calls
+            .split(',')
+            .map(|x| x.parse().unwrap())
+            .collect::<Vec<usize>>();
+        let boards = Bingo::parse_many(boards.as_bytes()).unwrap();
+        let outcome = BitmaskGame::new(&boards, calls.clone()).unwrap().play();
+        assert_eq!(outcome.first().unwrap().score, 38913);
+        assert_eq!(outcome.last().unwrap().score, 16836);
+        assert_same_outcome(boards, calls);
+    }
+
+    #[test]
+    fn test_random_boards_and_conditions() {
+        use WinCondition::*;
+
+        let conditions: [&[WinCondition]; 6] = [
+            &WinCondition::DEFAULT,
+            &[Diagonal],
+            &[FourCorners, Row],
+            &[FullCard],
+            &[Column, Diagonal, Row],
+            &[],
+        ];
+        let mut rng = Lcg(2021);
+        for _ in 0..20 {
+            let boards = (0..50)
+                .map(|_| {
+                    let (rows, cols) = (1 + rng.next(6), 1 + rng.next(6));
+                    let win_conditions = conditions[rng.next(conditions.len())];
+                    // Few enough values that boards have repeats and calls hit a lot of them.
+                    random_board(&mut rng, rows, cols, 40).with_win_conditions(win_conditions)
+                })
+                .collect::<Vec<_>>();
+            let calls = (0..60).map(|_| rng.next(45)).collect();
+            assert_same_outcome(boards, calls);
+        }
+    }
+
+    #[test]
+    fn test_many_boards() {
+        let mut rng = Lcg(4);
+        let boards = (0..2000)
+            .map(|_| random_board(&mut rng, 5, 5, 100))
+            .collect::<Vec<_>>();
+        let calls = (0..300).map(|_| rng.next(100)).collect();
+        assert_same_outcome(boards, calls);
+    }
+
+    #[test]
+    fn test_board_limits() {
+        let mut rng = Lcg(7);
+        let big = random_board(&mut rng, 8, 16, 1000);
+        assert!(BitmaskGame::new(&[big], vec![1]).is_some());
+        let too_big = random_board(&mut rng, 11, 12, 1000);
+        assert!(BitmaskGame::new(&[too_big], vec![1]).is_none());
+
+        let empty = BitmaskGame::new(&[], vec![1, 2, 3]).unwrap().play();
+        assert_eq!(empty.first(), None);
+    }
+}
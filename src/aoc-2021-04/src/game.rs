@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use crate::{Bingo, WinningLine};
+
+/// How and when a board won.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Win {
+    /// The index of the board among the boards in the game.
+    pub board: usize,
+    /// The index of the winning call among the calls.
+    pub call_idx: usize,
+    /// The number that was called to win.
+    pub number: usize,
+    pub line: WinningLine,
+    /// The winning number times the sum of the numbers that were still unmarked.
+    pub score: usize,
+}
+
+impl Display for Win {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "board {} won on call #{} ({}) with {}, scoring {}",
+            self.board, self.call_idx, self.number, self.line, self.score
+        )
+    }
+}
+
+/// Every win in a game of bingo, in the order the boards won.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Outcome {
+    pub(crate) wins: Vec<Win>,
+}
+
+impl Outcome {
+    /// The wins in order. Boards that won on the same call are ordered by their index.
+    pub fn wins(&self) -> &[Win] {
+        &self.wins
+    }
+
+    pub fn first(&self) -> Option<&Win> {
+        self.wins.first()
+    }
+
+    /// The last board to win, even if some boards never did.
+    pub fn last(&self) -> Option<&Win> {
+        self.wins.last()
+    }
+
+    /// The `k`-th board to win, counting from 0.
+    pub fn nth(&self, k: usize) -> Option<&Win> {
+        self.wins.get(k)
+    }
+
+    /// The win of the board at the given index, if it won at all.
+    pub fn for_board(&self, board: usize) -> Option<&Win> {
+        self.wins.iter().find(|win| win.board == board)
+    }
+}
+
+/// A game of bingo: some boards and the numbers to call on them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BingoGame {
+    boards: Vec<Bingo>,
+    calls: Vec<usize>,
+}
+
+impl BingoGame {
+    pub fn new(boards: Vec<Bingo>, calls: Vec<usize>) -> Self {
+        Self { boards, calls }
+    }
+
+    /// Call the numbers in order until every board has won or we run out of numbers.
+    /// A board stops being played as soon as it wins.
+    pub fn play(mut self) -> Outcome {
+        let mut wins = Vec::new();
+        let mut playing = (0..self.boards.len()).collect::<Vec<_>>();
+
+        for (call_idx, &number) in self.calls.iter().enumerate() {
+            playing.retain(|&board_idx| {
+                let board = &mut self.boards[board_idx];
+                board.play(number);
+                match board.winning_line() {
+                    Some(line) => {
+                        wins.push(Win {
+                            board: board_idx,
+                            call_idx,
+                            number,
+                            line,
+                            score: number * board.unplayed_sum(),
+                        });
+                        false
+                    }
+                    None => true,
+                }
+            });
+            if playing.is_empty() {
+                break;
+            }
+        }
+
+        Outcome { wins }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WinCondition;
+
+    const CALLS: [usize; 27] = [
+        7, 4, 9, 5, 11, 17, 23, 2, 0, 14, 21, 24, 10, 16, 13, 6, 15, 25, 12, 22, 18, 20, 8, 19, 3,
+        26, 1,
+    ];
+
+    const BOARDS: &str = "22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+    fn example() -> BingoGame {
+        BingoGame::new(
+            Bingo::parse_many(BOARDS.as_bytes()).unwrap(),
+            CALLS.to_vec(),
+        )
+    }
+
+    #[test]
+    fn test_example() {
+        let outcome = example().play();
+        assert_eq!(outcome.wins().len(), 3);
+
+        let first = outcome.first().unwrap();
+        assert_eq!(
+            *first,
+            Win {
+                board: 2,
+                call_idx: 11,
+                number: 24,
+                line: WinningLine::Row(0),
+                score: 4512,
+            }
+        );
+
+        let last = outcome.last().unwrap();
+        assert_eq!((last.board, last.number, last.score), (1, 13, 1924));
+        assert_eq!(last.line, WinningLine::Column(2));
+
+        let order = outcome
+            .wins()
+            .iter()
+            .map(|win| win.board)
+            .collect::<Vec<_>>();
+        assert_eq!(order, [2, 0, 1]);
+        assert_eq!(outcome.nth(1), outcome.for_board(0));
+        assert_eq!(outcome.nth(3), None);
+        assert_eq!(
+            first.to_string(),
+            "board 2 won on call #11 (24) with row 0, scoring 4512"
+        );
+    }
+
+    #[test]
+    fn test_parts() {
+        let outcome = example().play();
+        assert_eq!(crate::part_1::solve(&outcome), 4512);
+        assert_eq!(crate::part_2::solve(&outcome), 1924);
+    }
+
+    #[test]
+    fn test_boards_that_never_win() {
+        let outcome = BingoGame::new(
+            Bingo::parse_many(BOARDS.as_bytes()).unwrap(),
+            CALLS[..12].to_vec(),
+        )
+        .play();
+        assert_eq!(outcome.wins().len(), 1);
+        assert_eq!(outcome.first(), outcome.last());
+        assert_eq!(outcome.for_board(0), None);
+
+        let outcome = BingoGame::new(vec![], CALLS.to_vec()).play();
+        assert_eq!(outcome.first(), None);
+    }
+
+    #[test]
+    fn test_other_win_conditions() {
+        let boards = Bingo::parse_many(BOARDS.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|board| board.with_win_conditions(&[WinCondition::FourCorners]))
+            .collect();
+        let outcome = BingoGame::new(boards, CALLS.to_vec()).play();
+        assert_eq!(outcome.wins().len(), 3);
+        assert!(outcome
+            .wins()
+            .iter()
+            .all(|win| win.line == WinningLine::FourCorners));
+    }
+}
@@ -1,13 +1,107 @@
-use itertools::Itertools;
+use bitmask::BitmaskGame;
+use game::{BingoGame, Outcome};
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::io;
 use std::io::{BufRead, Read};
 
+mod bitmask;
+mod game;
+
+/// A way of completing a bingo board.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WinCondition {
+    /// Every number in some row has been called.
+    Row,
+    /// Every number in some column has been called.
+    Column,
+    /// Every number along one of the two diagonals has been called.
+    /// Only square boards have diagonals.
+    Diagonal,
+    /// Every number on the board has been called.
+    FullCard,
+    /// The numbers in all four corners have been called.
+    FourCorners,
+}
+
+impl WinCondition {
+    /// The rules from the puzzle: any complete row or column wins.
+    pub const DEFAULT: [WinCondition; 2] = [WinCondition::Row, WinCondition::Column];
+
+    /// Find the first line that satisfies this condition, if any.
+    pub fn find(&self, played: &[Vec<bool>]) -> Option<WinningLine> {
+        let rows = played.len();
+        let cols = played.first().map_or(0, Vec::len);
+        if rows == 0 || cols == 0 {
+            return None;
+        }
+
+        match self {
+            WinCondition::Row => played
+                .iter()
+                .position(|row| row.iter().all(|&x| x))
+                .map(WinningLine::Row),
+            WinCondition::Column => (0..cols)
+                .find(|&col_idx| played.iter().all(|row| row[col_idx]))
+                .map(WinningLine::Column),
+            WinCondition::Diagonal if rows != cols => None,
+            WinCondition::Diagonal => {
+                if (0..rows).all(|idx| played[idx][idx]) {
+                    Some(WinningLine::Diagonal)
+                } else if (0..rows).all(|idx| played[idx][cols - 1 - idx]) {
+                    Some(WinningLine::AntiDiagonal)
+                } else {
+                    None
+                }
+            }
+            WinCondition::FullCard => played
+                .iter()
+                .flatten()
+                .all(|&x| x)
+                .then_some(WinningLine::FullCard),
+            WinCondition::FourCorners => (played[0][0]
+                && played[0][cols - 1]
+                && played[rows - 1][0]
+                && played[rows - 1][cols - 1])
+                .then_some(WinningLine::FourCorners),
+        }
+    }
+}
+
+/// The marked numbers that won a board.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WinningLine {
+    /// The row at the given index.
+    Row(usize),
+    /// The column at the given index.
+    Column(usize),
+    /// The diagonal from the top left to the bottom right.
+    Diagonal,
+    /// The diagonal from the top right to the bottom left.
+    AntiDiagonal,
+    FullCard,
+    FourCorners,
+}
+
+impl Display for WinningLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinningLine::Row(row_idx) => write!(f, "row {}", row_idx),
+            WinningLine::Column(col_idx) => write!(f, "column {}", col_idx),
+            WinningLine::Diagonal => write!(f, "diagonal"),
+            WinningLine::AntiDiagonal => write!(f, "anti-diagonal"),
+            WinningLine::FullCard => write!(f, "full card"),
+            WinningLine::FourCorners => write!(f, "four corners"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Bingo {
     pub(crate) entries: Vec<Vec<usize>>,
     value_idx_map: HashMap<usize, (usize, usize)>,
-    played: [[bool; 5]; 5],
+    played: Vec<Vec<bool>>,
+    win_conditions: Vec<WinCondition>,
 }
 
 impl Default for Bingo {
@@ -21,8 +115,56 @@ impl Bingo {
         Self {
             entries: Vec::new(),
             value_idx_map: HashMap::<usize, (usize, usize)>::new(),
-            played: [[false; 5]; 5],
+            played: Vec::new(),
+            win_conditions: WinCondition::DEFAULT.to_vec(),
+        }
+    }
+
+    /// Build a board out of its rows, which should all be as long as each other.
+    pub fn from_rows(entries: Vec<Vec<usize>>) -> io::Result<Self> {
+        if let Some(first) = entries.first() {
+            if let Some(row) = entries.iter().find(|row| row.len() != first.len()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Rows should all have {} numbers but found {:?}",
+                        first.len(),
+                        row
+                    ),
+                ));
+            }
+        }
+
+        let mut value_idx_map: HashMap<usize, (usize, usize)> = HashMap::new();
+
+        for (row_idx, row) in entries.iter().enumerate() {
+            for (col_idx, &col) in row.iter().enumerate() {
+                value_idx_map.insert(col, (row_idx, col_idx));
+            }
         }
+
+        let played = entries.iter().map(|row| vec![false; row.len()]).collect();
+        Ok(Self {
+            entries,
+            value_idx_map,
+            played,
+            win_conditions: WinCondition::DEFAULT.to_vec(),
+        })
+    }
+
+    /// Win as soon as any of the given conditions is met, instead of on a complete row or column.
+    pub fn with_win_conditions(mut self, win_conditions: &[WinCondition]) -> Self {
+        self.win_conditions = win_conditions.to_vec();
+        self
+    }
+
+    pub fn win_conditions(&self) -> &[WinCondition] {
+        &self.win_conditions
+    }
+
+    /// The number of rows and columns on the board.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.entries.len(), self.entries.first().map_or(0, Vec::len))
     }
 
     pub fn play(&mut self, number: usize) {
@@ -33,120 +175,81 @@ impl Bingo {
     }
 
     pub fn unplayed_sum(&self) -> usize {
-        let mut result: usize = 0;
-
-        for row_idx in 0..5 {
-            for col_idx in 0..5 {
-                if !self.played[row_idx][col_idx] {
-                    result += self
-                        .entries
-                        .get(row_idx)
-                        .unwrap_or_else(|| panic!("Row idx: {:?} not found.", row_idx))
-                        .get(col_idx)
-                        .unwrap_or_else(|| {
-                            panic!("Row, Col idx: ({:?}, {:?}) not found.", row_idx, col_idx)
-                        })
-                }
-            }
-        }
-        result
+        self.entries
+            .iter()
+            .flatten()
+            .zip(self.played.iter().flatten())
+            .filter(|(_, &played)| !played)
+            .map(|(&entry, _)| entry)
+            .sum()
     }
-    pub fn has_finished(&self) -> bool {
-        // Check rows
-        for row in self.played {
-            // Looks weird but [true; 5] doesn't seem to work here.
-            if row == [true; 5] {
-                return true;
-            }
-        }
 
-        // Check columns.
-        for col_idx in 0..5 {
-            let mut current_col: [bool; 5] = [false; 5];
+    pub fn has_finished(&self) -> bool {
+        self.winning_line().is_some()
+    }
 
-            for (idx, item) in current_col.iter_mut().enumerate() {
-                *item = self.played[idx][col_idx];
-            }
-            if current_col == [true; 5] {
-                return true;
-            }
-        }
-        false
+    /// The line that won the board, trying the win conditions in order.
+    pub fn winning_line(&self) -> Option<WinningLine> {
+        self.win_conditions
+            .iter()
+            .find_map(|condition| condition.find(&self.played))
     }
 
+    /// Read a board made of the lines up to the first blank line after it
+    /// (or the end of the input), skipping any blank lines before it.
     pub fn parse<I: BufRead>(mut input: I) -> io::Result<Self> {
         let mut buffer: String = String::new();
         let mut whole_vec: Vec<Vec<usize>> = Vec::new();
 
-        for _ in 0..6 {
-            input.read_line(&mut buffer)?;
-            let current_vec: Vec<usize> = buffer
+        loop {
+            buffer.clear();
+            if input.read_line(&mut buffer)? == 0 {
+                break;
+            }
+            if buffer.trim().is_empty() {
+                if whole_vec.is_empty() {
+                    continue;
+                }
+                break;
+            }
+
+            let current_vec = buffer
                 .split_whitespace()
                 .map(|x| {
-                    x.parse::<usize>()
-                        .unwrap_or_else(|_| panic!("Could not parse: {:?}", x))
+                    x.parse::<usize>().map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("Could not parse: {:?}", x),
+                        )
+                    })
                 })
-                .collect::<Vec<usize>>();
-            if current_vec.len() == 5 {
-                whole_vec.push(current_vec);
-            }
-            buffer.clear();
+                .collect::<io::Result<Vec<usize>>>()?;
+            whole_vec.push(current_vec);
         }
 
-        let mut value_idx_map: HashMap<usize, (usize, usize)> = HashMap::new();
-
-        for (row_idx, row) in whole_vec.iter().enumerate() {
-            for (col_idx, &col) in row.iter().enumerate() {
-                value_idx_map.insert(col, (row_idx, col_idx));
-            }
-        }
-
-        Ok(Self {
-            entries: whole_vec,
-            value_idx_map,
-            played: [[false; 5]; 5],
-        })
+        Self::from_rows(whole_vec)
     }
 
+    /// Read every board in the input, separated by blank lines.
     pub fn parse_many<I: BufRead>(mut input: I) -> io::Result<Vec<Self>> {
-        let mut buffer: String = String::new();
-        input
-            .read_to_string(&mut buffer)
-            .expect("Couldn't read_to_string.");
-        let mut lines = buffer.lines().collect::<Vec<&str>>();
-
-        lines = lines
-            .into_iter()
-            .filter(|&x| x.split_whitespace().count() > 0)
-            .collect::<Vec<&str>>();
-
         let mut bingos: Vec<Bingo> = Vec::new();
-
-        if lines.len() % 5 != 0 {
-            panic!("Not enough lines: given ({:?})", lines.len());
-        }
-
-        for chunk in &lines.into_iter().chunks(5) {
-            let result = Self::parse(chunk.into_iter().join("\n").as_bytes());
-            match result {
-                Ok(bingo) => bingos.push(bingo),
-                Err(e) => {
-                    panic!("Wut? {:?}", e)
-                }
-            };
+        loop {
+            let bingo = Self::parse(&mut input)?;
+            if bingo.entries.is_empty() {
+                return Ok(bingos);
+            }
+            bingos.push(bingo);
         }
-        Ok(bingos)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::stdin;
 
     #[test]
     fn test_bingo_create() {
-        let mut b = Bingo::new();
+        let b = Bingo::new();
         println!("{:?}", b);
     }
 
@@ -205,6 +308,87 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_arbitrary_size_parse() {
+        let bingo_str = "
+        1 2 3 4
+        5 6 7 8
+        9 10 11 12
+
+        1 2
+        3 4
+        ";
+        let results = Bingo::parse_many(bingo_str.as_bytes()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].dimensions(), (3, 4));
+        assert_eq!(results[1].dimensions(), (2, 2));
+        assert_eq!(results[0].unplayed_sum(), 78);
+    }
+
+    #[test]
+    fn test_ragged_and_invalid_boards() {
+        let error = Bingo::parse("1 2 3\n4 5\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = Bingo::parse("1 2\n3 x\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    /// Play `calls` on a 3x4 board numbered 1 to 12, row by row.
+    fn play(win_conditions: &[WinCondition], calls: &[usize]) -> bool {
+        let mut bingo = Bingo::parse("1 2 3 4\n5 6 7 8\n9 10 11 12\n".as_bytes())
+            .unwrap()
+            .with_win_conditions(win_conditions);
+        for &c in calls {
+            bingo.play(c);
+        }
+        bingo.has_finished()
+    }
+
+    #[test]
+    fn test_win_conditions() {
+        use WinCondition::*;
+
+        assert!(play(&WinCondition::DEFAULT, &[5, 6, 7, 8]));
+        assert!(play(&WinCondition::DEFAULT, &[2, 6, 10]));
+        assert!(!play(&WinCondition::DEFAULT, &[1, 6, 11, 4]));
+
+        assert!(play(&[Row], &[5, 6, 7, 8]));
+        assert!(!play(&[Row], &[2, 6, 10]));
+        assert!(play(&[Column], &[2, 6, 10]));
+        assert!(!play(&[Column], &[5, 6, 7, 8]));
+
+        assert!(play(&[FourCorners], &[1, 4, 9, 12]));
+        assert!(!play(&[FourCorners], &[1, 4, 9]));
+
+        let all = (1..=12).collect::<Vec<_>>();
+        assert!(play(&[FullCard], &all));
+        assert!(!play(&[FullCard], &all[1..]));
+
+        // Boards that aren't square have no diagonals.
+        assert!(!play(&[Diagonal], &all));
+        assert!(!play(&[], &all));
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let board = "1 2 3\n4 5 6\n7 8 9\n";
+        for calls in [[1, 5, 9], [3, 5, 7]] {
+            let mut bingo = Bingo::parse(board.as_bytes())
+                .unwrap()
+                .with_win_conditions(&[WinCondition::Diagonal]);
+            for c in calls {
+                assert!(!bingo.has_finished());
+                bingo.play(c);
+            }
+            assert!(bingo.has_finished());
+        }
+        let mut bingo = Bingo::parse(board.as_bytes()).unwrap();
+        for c in [1, 5, 9] {
+            bingo.play(c);
+        }
+        assert!(!bingo.has_finished());
+    }
 }
 
 fn parse_bingo_from_stdin() -> Result<(Vec<usize>, Vec<Bingo>), io::Error> {
@@ -221,61 +405,69 @@ fn parse_bingo_from_stdin() -> Result<(Vec<usize>, Vec<Bingo>), io::Error> {
                 .unwrap_or_else(|_| panic!("Couldn't parse {:?}", x))
         })
         .collect::<Vec<usize>>();
-    let bingos: Vec<Bingo> = Bingo::parse_many(&mut buffer[instructions_endpoint + 1..].as_bytes())
+    let bingos: Vec<Bingo> = Bingo::parse_many(&buffer.as_bytes()[instructions_endpoint + 1..])
         .expect("Some bingo couldn't be parsed.");
 
     Ok((calls.to_vec(), bingos))
 }
 
 mod part_1 {
-    use super::Bingo;
+    use crate::game::Outcome;
 
-    pub fn solve(games: &mut [Bingo], calls: &[usize]) -> usize {
-        for &c in calls {
-            for board in games.iter_mut() {
-                board.play(c);
-                if board.has_finished() {
-                    return c * board.unplayed_sum();
-                }
-            }
-        }
-        0
+    pub fn solve(outcome: &Outcome) -> usize {
+        outcome.first().map_or(0, |win| win.score)
     }
 }
 
 mod part_2 {
-    use super::Bingo;
-    use std::collections::HashSet;
+    use crate::game::Outcome;
 
-    pub fn solve(games: &mut [Bingo], calls: &[usize]) -> usize {
-        let games_len: usize = games.len();
-        let mut last_winner: usize = usize::MAX;
-        let mut already_won: HashSet<usize> = HashSet::new();
-        let mut last_winning_call: usize = usize::MAX;
+    pub fn solve(outcome: &Outcome) -> usize {
+        outcome.last().map_or(0, |win| win.score)
+    }
+}
 
-        for &c in calls {
-            for (board_idx, board) in games.iter_mut().enumerate() {
-                board.play(c);
-                if board.has_finished() {
-                    if !already_won.contains(&board_idx) {
-                        already_won.insert(board_idx);
-                        last_winner = board_idx;
-                        last_winning_call = c;
-                    }
-                    if already_won.len() == games_len {
-                        return c * games[board_idx].unplayed_sum();
-                    }
-                }
-            }
+/// Print how every board did, in the order they were given.
+fn print_boards(outcome: &Outcome, boards: usize) {
+    for board in 0..boards {
+        match outcome.for_board(board) {
+            Some(win) => println!("{:}", win),
+            None => println!("board {:} never won", board),
         }
-        last_winning_call * games[last_winner].unplayed_sum()
     }
 }
 
 fn main() {
-    let (calls, mut games) = parse_bingo_from_stdin().expect("Parsing error.");
-    let result_part_1 = part_1::solve(&mut games.clone(), &calls);
-    let result_part_2 = part_2::solve(&mut games, &calls);
+    // Pass `--rank K` to also print the K-th board to win (counting from 1),
+    // or `--boards` to print how every board did.
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    let (calls, games) = parse_bingo_from_stdin().expect("Parsing error.");
+    let boards = games.len();
+    // The bitmask engine is a lot faster but can't handle huge boards.
+    let outcome = match BitmaskGame::new(&games, calls.clone()) {
+        Some(game) => game.play(),
+        None => BingoGame::new(games, calls).play(),
+    };
+    let result_part_1 = part_1::solve(&outcome);
+    let result_part_2 = part_2::solve(&outcome);
 
     println!("Part 1: {:}\nPart 2: {:}", result_part_1, result_part_2);
+
+    match args.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        [] => {}
+        ["--rank", k] => {
+            let k = k
+                .parse::<usize>()
+                .ok()
+                .filter(|&k| k > 0)
+                .unwrap_or_else(|| panic!("Rank should be a positive number, got {:?}", k));
+            match outcome.nth(k - 1) {
+                Some(win) => println!("Winner #{:}: {:}", k, win),
+                None => println!("Only {:} boards won.", outcome.wins().len()),
+            }
+        }
+        ["--boards"] => print_boards(&outcome, boards),
+        _ => panic!("Usage: aoc-2021-04 [--rank K | --boards] < input"),
+    }
 }